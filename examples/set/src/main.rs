@@ -20,7 +20,11 @@ fn main() {
 
     let mut mon = CallbackProgressMonitor::new(
         "root",
-        &[MyWork::ResourcesLoaded, MyWork::SystemInitialized],
+        &[
+            MyWork::ResourcesLoaded,
+            MyWork::ConnectionEstablished,
+            MyWork::SystemInitialized,
+        ],
         |a: &SetWork<MyWork>, w: &SetWork<MyWork>| tracing::info!("{}/{}", w, a),
     );
 
@@ -29,7 +33,8 @@ fn main() {
     thread::sleep(Duration::from_millis(500));
     mon.worked(MyWork::ConnectionEstablished);
     thread::sleep(Duration::from_millis(500));
-    // Submitting the same work twice is not a problem when working with sets. Could be an opt-in though!
+    // Submitting the same work twice is rejected. By default, a warning is logged and the work is ignored.
+    // Use `try_worked` to handle the error yourself.
     mon.worked(MyWork::ConnectionEstablished);
     thread::sleep(Duration::from_millis(500));
     mon.worked(MyWork::SystemInitialized);
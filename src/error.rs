@@ -1,4 +1,216 @@
-#[derive(Debug, Clone)]
-pub struct CloseError {
-    pub msg: String,
+use std::fmt::Display;
+
+use crate::work::AddError;
+
+/// Describes the monitor an error originated from. Work is kept in its rendered (`Display`) form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonitorContext {
+    pub name: String,
+    /// The names of all monitors from the root down to this monitor, including its own name.
+    pub path: Vec<String>,
+    pub total: String,
+    pub completed: String,
+    pub remaining: String,
+}
+
+impl Display for MonitorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "'{}' ({}/{}, {} remaining)",
+            self.path.join("/"),
+            self.completed,
+            self.total,
+            self.remaining
+        )
+    }
+}
+
+/// Reasons for a monitor not being closed successfully.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CloseError {
+    /// The monitor was closed while work was left.
+    Incomplete { monitor: Box<MonitorContext> },
+    /// A detached child monitor disconnected without being closed (successfully). See `DetachedChildReceiver`.
+    Disconnected { name: String },
+}
+
+impl CloseError {
+    /// The name of the monitor which could not be closed.
+    pub fn name(&self) -> &str {
+        match self {
+            CloseError::Incomplete { monitor } => &monitor.name,
+            CloseError::Disconnected { name } => name,
+        }
+    }
+}
+
+impl Display for CloseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CloseError::Incomplete { monitor } => {
+                write!(f, "Must not close monitor {monitor} while work is left.")
+            }
+            CloseError::Disconnected { name } => write!(
+                f,
+                "Detached child monitor '{name}' disconnected without being closed."
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CloseError {}
+
+/// Reasons for rejecting work submitted to a monitor. See `ProgressMonitor::try_worked`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorkError {
+    /// The work submitted contains an item which was already done.
+    DuplicateItem {
+        monitor: Box<MonitorContext>,
+        item: String,
+    },
+    /// The work done would no longer be representable by the type of work.
+    Overflow { monitor: Box<MonitorContext> },
+    /// The monitor was already closed successfully.
+    Closed { monitor: Box<MonitorContext> },
+    /// The work submitted contains an item which is not part of the total work of the monitor.
+    UnknownItem {
+        monitor: Box<MonitorContext>,
+        item: String,
+    },
+    /// The work submitted exceeds the work remaining. Only reported when using `OvershootPolicy::Error`.
+    Overshoot {
+        monitor: Box<MonitorContext>,
+        submitted: String,
+    },
+    /// The work taken back was not completed. See `ProgressMonitor::try_unworked`.
+    NotCompleted {
+        monitor: Box<MonitorContext>,
+        submitted: String,
+    },
+    /// The new total does not cover the work already completed. See `ProgressMonitor::try_set_total`.
+    TotalBelowCompleted {
+        monitor: Box<MonitorContext>,
+        total: String,
+    },
+}
+
+impl WorkError {
+    pub(crate) fn from_add(err: AddError, monitor: MonitorContext) -> Self {
+        let monitor = Box::new(monitor);
+        match err {
+            AddError::Duplicate { item } => WorkError::DuplicateItem { monitor, item },
+            AddError::Overflow => WorkError::Overflow { monitor },
+        }
+    }
+
+    /// The monitor which rejected the work.
+    pub fn monitor(&self) -> &MonitorContext {
+        match self {
+            WorkError::DuplicateItem { monitor, .. }
+            | WorkError::Overflow { monitor }
+            | WorkError::Closed { monitor }
+            | WorkError::UnknownItem { monitor, .. }
+            | WorkError::Overshoot { monitor, .. }
+            | WorkError::NotCompleted { monitor, .. }
+            | WorkError::TotalBelowCompleted { monitor, .. } => monitor,
+        }
+    }
+}
+
+impl Display for WorkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorkError::DuplicateItem { monitor, item } => {
+                write!(f, "Item {item} is already done in monitor {monitor}.")
+            }
+            WorkError::Overflow { monitor } => {
+                write!(f, "Work done overflows in monitor {monitor}.")
+            }
+            WorkError::Closed { monitor } => write!(f, "Monitor {monitor} is already closed."),
+            WorkError::UnknownItem { monitor, item } => {
+                write!(
+                    f,
+                    "Item {item} is not part of the work of monitor {monitor}."
+                )
+            }
+            WorkError::Overshoot { monitor, submitted } => write!(
+                f,
+                "Work of {submitted} exceeds the remaining work of monitor {monitor}."
+            ),
+            WorkError::NotCompleted { monitor, submitted } => write!(
+                f,
+                "Work of {submitted} taken back was not completed in monitor {monitor}."
+            ),
+            WorkError::TotalBelowCompleted { monitor, total } => write!(
+                f,
+                "Total of {total} does not cover the work already completed in monitor {monitor}."
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WorkError {}
+
+/// Returned when a child requests more work of its parent than the parent has remaining.
+/// See `ProgressMonitorDivision::try_new_child`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewChildError {
+    /// The parent monitor.
+    pub parent: Box<MonitorContext>,
+    /// The work of the parent which is neither done nor handed out to other children.
+    pub available: String,
+    /// The work of the parent requested by the child.
+    pub requested: String,
+}
+
+impl Display for NewChildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Child of monitor {} requested {} of its work, but only {} is available.",
+            self.parent, self.requested, self.available
+        )
+    }
+}
+
+impl std::error::Error for NewChildError {}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+
+    fn assert_error<E: std::error::Error + Send + Sync + 'static>(_: &E) {}
+
+    #[test]
+    fn close_error_describes_monitor() {
+        let mut root = CallbackProgressMonitor::silent("root", 10);
+        {
+            let mut child = root.new_child("child", 10u64, 4u64);
+            child.worked(1u64);
+            let err = child.close().unwrap_err();
+            assert_error(&err);
+            match &err {
+                CloseError::Incomplete { monitor } => {
+                    assert_eq!(monitor.path, ["root", "child"]);
+                    assert_eq!(monitor.completed, "1");
+                    assert_eq!(monitor.remaining, "3");
+                }
+                other => panic!("Expected incomplete, got {other:?}"),
+            }
+            assert_eq!(
+                err.to_string(),
+                "Must not close monitor 'root/child' (1/4, 3 remaining) while work is left."
+            );
+            {
+                let mut grandchild = child.new_child("grandchild", 3u64, 1u64);
+                assert_eq!(grandchild.path(), ["root", "child", "grandchild"]);
+                grandchild.worked(1u64);
+                grandchild.close().unwrap();
+            }
+            assert_eq!(child.completed().as_ref(), &NumericWork::new(4u64));
+            child.close().unwrap();
+        }
+        root.close().unwrap();
+    }
 }
@@ -2,17 +2,44 @@ use error::CloseError;
 
 pub mod error;
 pub mod monitor;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+pub mod process;
+#[cfg(feature = "registry")]
+pub mod registry;
+#[cfg(all(unix, feature = "remote"))]
+pub mod remote;
 pub mod work;
 
 pub mod prelude {
-    pub use crate::error::CloseError;
+    pub use crate::error::{CloseError, MonitorContext, NewChildError, WorkError};
+    pub use crate::monitor::atomic::AtomicProgressMonitor;
     pub use crate::monitor::callback::CallbackProgressMonitor;
+    pub use crate::monitor::cancel::CancellationToken;
+    pub use crate::monitor::config::{
+        DropPolicy, ExcessSharePolicy, InvalidWorkPolicy, MonitorConfig, OvershootPolicy,
+    };
+    pub use crate::monitor::detached::{DetachedChildMonitor, DetachedChildReceiver, DetachedChildStatus};
+    pub use crate::monitor::shared::SharedProgressMonitor;
     pub use crate::monitor::sub::ChildMonitor;
+    pub use crate::monitor::subscription::{ProgressSnapshot, Subscription};
+    pub use crate::monitor::CloseOutcome;
     pub use crate::monitor::ProgressMonitor;
     pub use crate::monitor::ProgressMonitorDivision;
+    pub use crate::process::{ProgressCommand, ProgressLine, ProgressSource};
+    pub use crate::work::bytes::ByteWork;
+    pub use crate::work::duration::DurationWork;
+    pub use crate::work::float::FloatWork;
     pub use crate::work::numeric::NumericWork;
     pub use crate::work::set::SetWork;
     pub use crate::work::Work;
+
+    #[cfg(feature = "async")]
+    pub use crate::monitor::asynchronous::AsyncProgressMonitor;
+    #[cfg(feature = "rayon")]
+    pub use crate::parallel::{ConcurrentProgressMonitor, ParallelProgressIterator};
+    #[cfg(all(unix, feature = "remote"))]
+    pub use crate::remote::{ProgressServer, RemoteProgressMonitor};
 }
 
 #[cfg(test)]
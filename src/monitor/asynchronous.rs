@@ -0,0 +1,344 @@
+use std::{
+    borrow::Cow,
+    fmt::{Debug, Display},
+    future::Future,
+};
+
+use tokio::sync::watch;
+
+use crate::{
+    error::{NewChildError, WorkError},
+    work::Work,
+    CloseError,
+};
+
+use super::{
+    cancel::CancellationToken,
+    clock::Clock,
+    config::MonitorConfig,
+    shared::{SharedCallback, SharedProgressMonitor},
+    subscription::Subscription,
+    CloseOutcome, ProgressMonitor,
+};
+
+/// A progress monitor for async code. Requires the `async` feature.
+///
+/// The monitor can be cloned and moved into spawned tasks. Children created through `new_child` or `split` are `'static`
+/// and can be handed to spawned tasks as well. Submitting work never blocks or awaits.
+///
+/// Progress is reported to an async callback, which is run by the driver future returned from `new`.
+/// The driver must be awaited or spawned. It awaits each callback before reporting further progress,
+/// coalescing all progress made in the meantime into a single report. It finishes once the monitor and all its children are gone.
+pub struct AsyncProgressMonitor<W: Work> {
+    inner: SharedProgressMonitor<'static, W>,
+}
+
+impl<W> AsyncProgressMonitor<W>
+where
+    W: Work + Send + Sync + 'static,
+{
+    pub fn new<N, A, C, F>(
+        name: N,
+        work: A,
+        callback: C,
+    ) -> (Self, impl Future<Output = ()> + Send + 'static)
+    where
+        N: Into<Cow<'static, str>>,
+        A: Into<W>,
+        C: Fn(W, W) -> F + Send + 'static,
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let work: W = work.into();
+        let (report, driver) = Self::driver(work.clone(), callback);
+        let inner = SharedProgressMonitor::new(name, work, report);
+        (Self { inner }, driver)
+    }
+
+    /// A monitor whose total is unknown. See `CallbackProgressMonitor::indeterminate`.
+    /// The callback is handed a total of zero until a total is set.
+    pub fn indeterminate<N, C, F>(
+        name: N,
+        callback: C,
+    ) -> (Self, impl Future<Output = ()> + Send + 'static)
+    where
+        N: Into<Cow<'static, str>>,
+        C: Fn(W, W) -> F + Send + 'static,
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let (report, driver) = Self::driver(W::zero(), callback);
+        let inner = SharedProgressMonitor::indeterminate(name, report);
+        (Self { inner }, driver)
+    }
+
+    /// Creates the callback of the monitor, reporting to the driver future which runs `callback`.
+    fn driver<C, F>(
+        work: W,
+        callback: C,
+    ) -> (
+        SharedCallback<'static, W>,
+        impl Future<Output = ()> + Send + 'static,
+    )
+    where
+        C: Fn(W, W) -> F + Send + 'static,
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let (sender, mut receiver) = watch::channel((work, W::zero()));
+        let report = Box::new(move |work: &W, work_done: &W| {
+            sender.send_replace((work.clone(), work_done.clone()));
+        });
+        let driver = async move {
+            while receiver.changed().await.is_ok() {
+                let (work, work_done) = receiver.borrow_and_update().clone();
+                // Borrowing `callback` only while creating the future keeps the driver `Send`.
+                let reported = callback(work, work_done);
+                reported.await;
+            }
+        };
+        (report, driver)
+    }
+
+    pub fn name(&self) -> Cow<'static, str> {
+        self.inner.name()
+    }
+
+    pub fn with_config(self, config: MonitorConfig) -> Self {
+        Self {
+            inner: self.inner.with_config(config),
+        }
+    }
+
+    /// Observe the progress of this monitor from somewhere else. Use `Subscription::changed` to await changes.
+    pub fn subscribe(&self) -> Subscription<W> {
+        self.inner.subscribe()
+    }
+
+    /// Create a child monitor, responsible for `parent_work` of this monitors work, using its own scale of `child_work`.
+    /// See `SharedProgressMonitor::new_child`.
+    pub fn new_child<N, A1, A2>(&self, name: N, parent_work: A1, child_work: A2) -> Self
+    where
+        N: Into<Cow<'static, str>>,
+        A1: Into<W>,
+        A2: Into<W>,
+    {
+        Self {
+            inner: self.inner.new_child(name, parent_work, child_work),
+        }
+    }
+
+    /// Like `new_child`, but fails instead of panicking. See `SharedProgressMonitor::try_new_child`.
+    pub fn try_new_child<N, A1, A2>(
+        &self,
+        name: N,
+        parent_work: A1,
+        child_work: A2,
+    ) -> Result<Self, NewChildError>
+    where
+        N: Into<Cow<'static, str>>,
+        A1: Into<W>,
+        A2: Into<W>,
+    {
+        Ok(Self {
+            inner: self.inner.try_new_child(name, parent_work, child_work)?,
+        })
+    }
+
+    /// Create a child whose total is unknown. See `SharedProgressMonitor::new_indeterminate_child`.
+    pub fn new_indeterminate_child<N, A>(&self, name: N, parent_work: A) -> Self
+    where
+        N: Into<Cow<'static, str>>,
+        A: Into<W>,
+    {
+        Self {
+            inner: self.inner.new_indeterminate_child(name, parent_work),
+        }
+    }
+
+    /// Like `new_indeterminate_child`, but fails instead of panicking.
+    /// See `SharedProgressMonitor::try_new_indeterminate_child`.
+    pub fn try_new_indeterminate_child<N, A>(
+        &self,
+        name: N,
+        parent_work: A,
+    ) -> Result<Self, NewChildError>
+    where
+        N: Into<Cow<'static, str>>,
+        A: Into<W>,
+    {
+        Ok(Self {
+            inner: self.inner.try_new_indeterminate_child(name, parent_work)?,
+        })
+    }
+
+    /// Create multiple children at once. See `SharedProgressMonitor::split`.
+    pub fn split<N, A1, A2, I>(&self, parts: I) -> Vec<Self>
+    where
+        N: Into<Cow<'static, str>>,
+        A1: Into<W>,
+        A2: Into<W>,
+        I: IntoIterator<Item = (N, A1, A2)>,
+    {
+        self.inner
+            .split(parts)
+            .into_iter()
+            .map(|inner| Self { inner })
+            .collect()
+    }
+}
+
+impl<W: Work> Clone for AsyncProgressMonitor<W> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<W: Work> ProgressMonitor<W> for AsyncProgressMonitor<W> {
+    fn name(&self) -> Cow<'_, str> {
+        ProgressMonitor::name(&self.inner)
+    }
+
+    fn try_worked<A: Into<W>>(&mut self, amount_of_work: A) -> Result<(), WorkError> {
+        self.inner.try_worked(amount_of_work)
+    }
+
+    fn try_unworked<A: Into<W>>(&mut self, amount_of_work: A) -> Result<(), WorkError> {
+        self.inner.try_unworked(amount_of_work)
+    }
+
+    fn total(&self) -> Cow<'_, W> {
+        self.inner.total()
+    }
+
+    fn try_set_total<A: Into<W>>(&mut self, total: A) -> Result<(), WorkError> {
+        self.inner.try_set_total(total)
+    }
+
+    fn completed(&self) -> Cow<'_, W> {
+        self.inner.completed()
+    }
+
+    fn remaining(&self) -> Cow<'_, W> {
+        self.inner.remaining()
+    }
+
+    fn close(&mut self) -> Result<CloseOutcome, CloseError> {
+        self.inner.close()
+    }
+
+    fn pause(&mut self) {
+        self.inner.pause()
+    }
+
+    fn resume(&mut self) {
+        self.inner.resume()
+    }
+
+    fn clock(&self) -> Clock {
+        self.inner.clock()
+    }
+
+    fn skip<R: Into<String>>(&mut self, reason: R) -> CloseOutcome {
+        self.inner.skip(reason)
+    }
+
+    fn is_indeterminate(&self) -> bool {
+        self.inner.is_indeterminate()
+    }
+
+    fn cancellation_token(&self) -> CancellationToken {
+        self.inner.cancellation_token()
+    }
+
+    fn config(&self) -> MonitorConfig {
+        self.inner.config()
+    }
+
+    fn path(&self) -> Vec<String> {
+        self.inner.path()
+    }
+
+    #[cfg(feature = "registry")]
+    fn registry_id(&self) -> Option<crate::registry::MonitorId> {
+        self.inner.registry_id()
+    }
+}
+
+impl<W: Work> Debug for AsyncProgressMonitor<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("AsyncProgressMonitor")
+            .field(&self.inner)
+            .finish()
+    }
+}
+
+impl<W: Work> Display for AsyncProgressMonitor<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.inner, f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use crate::prelude::*;
+
+    #[tokio::test]
+    async fn children_work_in_spawned_tasks() {
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let (mut mon, driver) = AsyncProgressMonitor::new("root", 100u64, {
+            let reported = reported.clone();
+            move |_: NumericWork<u64>, done: NumericWork<u64>| {
+                let reported = reported.clone();
+                async move { reported.lock().unwrap().push(done) }
+            }
+        });
+        let driver = tokio::spawn(driver);
+
+        let tasks: Vec<_> = mon
+            .split([("a", 50u64, 10u64), ("b", 50u64, 10u64)])
+            .into_iter()
+            .map(|mut child| {
+                tokio::spawn(async move {
+                    child.worked(10u64);
+                    child.close().unwrap();
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+        mon.close().unwrap();
+        drop(mon);
+        driver.await.unwrap();
+
+        let reported = reported.lock().unwrap();
+        assert_eq!(reported.last(), Some(&NumericWork::new(100u64)));
+    }
+
+    #[tokio::test]
+    async fn indeterminate_monitors_report_a_total_of_zero() {
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let (mut mon, driver) = AsyncProgressMonitor::indeterminate("root", {
+            let reported = reported.clone();
+            move |total: NumericWork<u64>, done: NumericWork<u64>| {
+                let reported = reported.clone();
+                async move { reported.lock().unwrap().push((total, done)) }
+            }
+        });
+        let driver = tokio::spawn(driver);
+        mon.worked(7u64);
+        assert!(mon.is_indeterminate());
+        assert_eq!(mon.to_string(), "7/?");
+        mon.close().unwrap();
+        drop(mon);
+        driver.await.unwrap();
+
+        let reported = reported.lock().unwrap();
+        assert_eq!(
+            reported.last(),
+            Some(&(NumericWork::zero(), NumericWork::new(7u64)))
+        );
+    }
+}
@@ -0,0 +1,466 @@
+use std::{
+    borrow::Cow,
+    fmt::{Debug, Display},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, PoisonError,
+    },
+};
+
+use crate::{error::WorkError, prelude::NumericWork, work::Work, CloseError};
+
+use super::{
+    cancel::CancellationToken,
+    clock::Clock,
+    config::{handle_drop, MonitorConfig, OvershootPolicy},
+    subscription::{ProgressSnapshot, Subscribers, Subscription},
+    CloseOutcome, ProgressMonitor, ProgressMonitorDivision,
+};
+
+/// A lock-free monitor for counting work, meant to be shared between threads.
+///
+/// Work can be submitted through a shared reference using `add`, without taking any lock.
+/// The callback is only fired whenever the work done crosses a multiple of the report step (1% of the total work by default),
+/// so it is called at most `total / step + 1` times, independent of how often work is submitted.
+///
+/// A monitor created with a total of zero accepts a total inferred later on, e.g. from the length of a parallel iterator.
+/// See `infer_total`.
+/// The callback may be called concurrently from multiple threads and calls may arrive out of order.
+/// Subscriptions are updated as often as the callback is called.
+pub struct AtomicProgressMonitor<'n, C: Fn(&NumericWork<u64>, &NumericWork<u64>) + Sync> {
+    name: Cow<'n, str>,
+    work: AtomicU64,
+    /// May exceed `work` when overshooting. Always read through `work_done()`.
+    work_done: AtomicU64,
+    /// Defaults to 1% of the total work.
+    report_step: Option<u64>,
+    callback: C,
+    closed: Option<Result<CloseOutcome, CloseError>>,
+    config: MonitorConfig,
+    token: CancellationToken,
+    clock: Clock,
+    /// Only locked when reporting.
+    subscribers: Mutex<Subscribers<NumericWork<u64>>>,
+}
+
+impl<'n, C> AtomicProgressMonitor<'n, C>
+where
+    C: Fn(&NumericWork<u64>, &NumericWork<u64>) + Sync,
+{
+    pub fn new<N: Into<Cow<'n, str>>>(name: N, work: u64, callback: C) -> Self {
+        Self {
+            name: name.into(),
+            work: AtomicU64::new(work),
+            work_done: AtomicU64::new(0),
+            report_step: None,
+            callback,
+            closed: None,
+            config: MonitorConfig::default(),
+            token: CancellationToken::new(),
+            clock: Clock::start(),
+            subscribers: Mutex::new(Subscribers::new()),
+        }
+    }
+
+    /// Fire the callback whenever the work done crosses a multiple of `report_step`.
+    pub fn with_report_step(mut self, report_step: u64) -> Self {
+        self.report_step = Some(report_step.max(1));
+        self
+    }
+
+    pub fn with_config(mut self, config: MonitorConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Sets the total amount of work to `work`, if this monitor was created with a total of zero and no work was done yet.
+    /// Returns whether the total was set.
+    pub fn infer_total(&self, work: u64) -> bool {
+        if self.work_done.load(Ordering::Relaxed) != 0 {
+            return false;
+        }
+        self.work
+            .compare_exchange(0, work, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    pub fn name(&self) -> Cow<'n, str> {
+        self.name.clone()
+    }
+
+    /// Observe the progress of this monitor from somewhere else, e.g. another thread.
+    pub fn subscribe(&self) -> Subscription<NumericWork<u64>> {
+        let snapshot = self.snapshot(self.work_done());
+        self.lock_subscribers().subscribe(snapshot)
+    }
+
+    fn lock_subscribers(&self) -> std::sync::MutexGuard<'_, Subscribers<NumericWork<u64>>> {
+        self.subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn snapshot(&self, work_done: u64) -> ProgressSnapshot<NumericWork<u64>> {
+        ProgressSnapshot {
+            name: self.name.to_string(),
+            total: NumericWork::new(self.work()),
+            completed: NumericWork::new(work_done),
+            closed: self.closed.clone(),
+            paused: self.clock.is_paused(),
+            indeterminate: false,
+            active_time: self.clock.active_time(),
+        }
+    }
+
+    fn publish(&self, work_done: u64) {
+        let mut subscribers = self.lock_subscribers();
+        if !subscribers.is_empty() {
+            subscribers.publish(self.snapshot(work_done));
+        }
+    }
+
+    /// Submit work without requiring exclusive access to this monitor.
+    /// Invalid work is handled according to the configured `InvalidWorkPolicy`. See `try_add`.
+    pub fn add(&self, amount: u64) {
+        if let Err(err) = self.try_add(amount) {
+            self.config.on_invalid_work.handle(&err, self);
+        }
+    }
+
+    /// Submit work without requiring exclusive access to this monitor.
+    /// Fails if the monitor was closed, the work done would overflow or on overshoot when using `OvershootPolicy::Error`.
+    pub fn try_add(&self, amount: u64) -> Result<(), WorkError> {
+        if let Some(Ok(_)) = self.closed {
+            return Err(WorkError::Closed {
+                monitor: Box::new(self.context()),
+            });
+        }
+        let mut total = self.work();
+        if self.config.on_overshoot == OvershootPolicy::Error {
+            return self.try_add_within(amount, total);
+        }
+        let before = self.work_done.fetch_add(amount, Ordering::Relaxed);
+        let after = match before.checked_add(amount) {
+            Some(after) => after,
+            None => {
+                // Wrapping arithmetic lets us take back what we just added.
+                self.work_done.fetch_sub(amount, Ordering::Relaxed);
+                return Err(WorkError::Overflow {
+                    monitor: Box::new(self.context()),
+                });
+            }
+        };
+
+        if after > total {
+            match self.config.on_overshoot {
+                OvershootPolicy::ClampSilently => {}
+                OvershootPolicy::ClampAndWarn => {
+                    // Only the call crossing the total reports the overshoot.
+                    if before <= total {
+                        tracing::warn!(
+                            work = total,
+                            work_done = before,
+                            new_work_done = amount,
+                            would_become = after,
+                            "Detected overshoot. Try to only submit work left open. Ignoring additional work."
+                        );
+                    }
+                }
+                OvershootPolicy::Error => unreachable!("Checked before adding"),
+                OvershootPolicy::Panic => panic!(
+                    "Detected overshoot in {self:?}. Submitted {amount} when only {} was remaining.",
+                    total.saturating_sub(before)
+                ),
+                OvershootPolicy::GrowTotal => {
+                    total = self.work.fetch_max(after, Ordering::Relaxed).max(after);
+                }
+            }
+            // Calls after the one crossing the total are ignored silently.
+            if before > total {
+                return Ok(());
+            }
+        }
+
+        self.report(before, after.min(total), total);
+        Ok(())
+    }
+
+    /// Adds `amount` only if the work done stays within `total`. Used with `OvershootPolicy::Error`,
+    /// so that rejected work is never added, not even temporarily, and cannot make valid work of other threads overshoot.
+    fn try_add_within(&self, amount: u64, total: u64) -> Result<(), WorkError> {
+        let mut before = self.work_done.load(Ordering::Relaxed);
+        let after = loop {
+            let after = before
+                .checked_add(amount)
+                .ok_or_else(|| WorkError::Overflow {
+                    monitor: Box::new(self.context()),
+                })?;
+            if after > total {
+                return Err(WorkError::Overshoot {
+                    monitor: Box::new(self.context()),
+                    submitted: amount.to_string(),
+                });
+            }
+            match self.work_done.compare_exchange_weak(
+                before,
+                after,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break after,
+                Err(current) => before = current,
+            }
+        };
+        self.report(before, after, total);
+        Ok(())
+    }
+
+    /// Fires the callback if the work done advanced from `before` to `after` crossed a report step or finished the monitor.
+    fn report(&self, before: u64, after: u64, total: u64) {
+        let report_step = self.report_step.unwrap_or(total / 100).max(1);
+        let crossed_step = before / report_step != after / report_step;
+        let finished = before < total && after == total;
+        if crossed_step || finished {
+            (self.callback)(&NumericWork::new(total), &NumericWork::new(after));
+            self.publish(after);
+        }
+    }
+
+    fn work(&self) -> u64 {
+        self.work.load(Ordering::Relaxed)
+    }
+
+    fn work_done(&self) -> u64 {
+        self.work_done.load(Ordering::Relaxed).min(self.work())
+    }
+}
+
+impl<'n, C> ProgressMonitor<NumericWork<u64>> for AtomicProgressMonitor<'n, C>
+where
+    C: Fn(&NumericWork<u64>, &NumericWork<u64>) + Sync,
+{
+    fn name(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.name)
+    }
+
+    fn try_worked<A: Into<NumericWork<u64>>>(
+        &mut self,
+        amount_of_work: A,
+    ) -> Result<(), WorkError> {
+        self.try_add(*amount_of_work.into().value())
+    }
+
+    fn try_unworked<A: Into<NumericWork<u64>>>(
+        &mut self,
+        amount_of_work: A,
+    ) -> Result<(), WorkError> {
+        let amount = *amount_of_work.into().value();
+        if let Some(Ok(_)) = self.closed {
+            return Err(WorkError::Closed {
+                monitor: Box::new(self.context()),
+            });
+        }
+        // Forget about work exceeding the total, it must not count towards the work left.
+        let work_done = self.work_done();
+        if amount > work_done {
+            return Err(WorkError::NotCompleted {
+                monitor: Box::new(self.context()),
+                submitted: amount.to_string(),
+            });
+        }
+        let work_done = work_done - amount;
+        *self.work_done.get_mut() = work_done;
+        (self.callback)(&NumericWork::new(self.work()), &NumericWork::new(work_done));
+        self.publish(work_done);
+        Ok(())
+    }
+
+    fn total(&self) -> Cow<'_, NumericWork<u64>> {
+        Cow::Owned(NumericWork::new(self.work()))
+    }
+
+    fn try_set_total<A: Into<NumericWork<u64>>>(&mut self, total: A) -> Result<(), WorkError> {
+        let total = *total.into().value();
+        if let Some(Ok(_)) = self.closed {
+            return Err(WorkError::Closed {
+                monitor: Box::new(self.context()),
+            });
+        }
+        // Forget about work exceeding the previous total, it must not count towards the new total.
+        let work_done = self.work_done();
+        *self.work_done.get_mut() = work_done;
+        if work_done > total {
+            return Err(WorkError::TotalBelowCompleted {
+                monitor: Box::new(self.context()),
+                total: total.to_string(),
+            });
+        }
+        *self.work.get_mut() = total;
+        (self.callback)(&NumericWork::new(total), &NumericWork::new(work_done));
+        self.publish(work_done);
+        Ok(())
+    }
+
+    fn completed(&self) -> Cow<'_, NumericWork<u64>> {
+        Cow::Owned(NumericWork::new(self.work_done()))
+    }
+
+    fn remaining(&self) -> Cow<'_, NumericWork<u64>> {
+        Cow::Owned(NumericWork::new(self.work() - self.work_done()))
+    }
+
+    fn close(&mut self) -> Result<CloseOutcome, CloseError> {
+        if let Some(Ok(outcome)) = &self.closed {
+            return Ok(outcome.clone());
+        }
+        let result = if self.remaining().as_ref() == &NumericWork::zero() {
+            Ok(CloseOutcome::Completed)
+        } else if self.token.is_cancelled() {
+            Ok(CloseOutcome::Cancelled)
+        } else {
+            Err(CloseError::Incomplete {
+                monitor: Box::new(self.context()),
+            })
+        };
+        if result.is_ok() {
+            self.clock.stop();
+        }
+        self.closed = Some(result.clone());
+        self.publish(self.work_done());
+        result
+    }
+
+    fn pause(&mut self) {
+        if self.clock.pause() {
+            self.publish(self.work_done());
+        }
+    }
+
+    fn resume(&mut self) {
+        if self.clock.resume() {
+            self.publish(self.work_done());
+        }
+    }
+
+    fn clock(&self) -> Clock {
+        self.clock
+    }
+
+    fn skip<R: Into<String>>(&mut self, reason: R) -> CloseOutcome {
+        if let Some(Ok(outcome)) = &self.closed {
+            return outcome.clone();
+        }
+        let outcome = CloseOutcome::Skipped {
+            reason: reason.into(),
+        };
+        self.clock.stop();
+        self.closed = Some(Ok(outcome.clone()));
+        self.publish(self.work_done());
+        outcome
+    }
+
+    fn cancellation_token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    fn config(&self) -> MonitorConfig {
+        self.config
+    }
+}
+
+impl<'p, 'n, N, A1, A2, C> ProgressMonitorDivision<'p, 'n, N, NumericWork<u64>, A1, A2>
+    for AtomicProgressMonitor<'n, C>
+where
+    N: Into<Cow<'n, str>>,
+    A1: Into<NumericWork<u64>>,
+    A2: Into<NumericWork<u64>>,
+    C: Fn(&NumericWork<u64>, &NumericWork<u64>) + Sync,
+{
+}
+
+impl<'n, C> Debug for AtomicProgressMonitor<'n, C>
+where
+    C: Fn(&NumericWork<u64>, &NumericWork<u64>) + Sync,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AtomicProgressMonitor")
+            .field("name", &self.name)
+            .field("work", &self.work())
+            .field("work_done", &self.work_done())
+            .finish()
+    }
+}
+
+impl<'n, C> Display for AtomicProgressMonitor<'n, C>
+where
+    C: Fn(&NumericWork<u64>, &NumericWork<u64>) + Sync,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}/{}", self.work_done(), self.work()))
+    }
+}
+
+impl<'n, C> Drop for AtomicProgressMonitor<'n, C>
+where
+    C: Fn(&NumericWork<u64>, &NumericWork<u64>) + Sync,
+{
+    fn drop(&mut self) {
+        let closed = self.closed.clone();
+        handle_drop(self, closed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        thread,
+    };
+
+    use crate::prelude::*;
+
+    #[test]
+    fn callbacks_are_bounded() {
+        let calls = AtomicUsize::new(0);
+        let mut mon = AtomicProgressMonitor::new("root", 400_000, |_, _| {
+            calls.fetch_add(1, Ordering::Relaxed);
+        });
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..100_000 {
+                        mon.add(1);
+                    }
+                });
+            }
+        });
+        assert_eq!(mon.completed().as_ref(), &NumericWork::new(400_000u64));
+        assert_eq!(calls.load(Ordering::Relaxed), 100);
+        mon.close().unwrap();
+    }
+
+    #[test]
+    fn rejected_work_does_not_affect_other_threads() {
+        let mut mon =
+            AtomicProgressMonitor::new("root", 1000, |_, _| {}).with_config(MonitorConfig {
+                on_overshoot: OvershootPolicy::Error,
+                ..MonitorConfig::default()
+            });
+        thread::scope(|s| {
+            s.spawn(|| {
+                for _ in 0..10_000 {
+                    assert!(mon.try_add(1001).is_err());
+                }
+            });
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..250 {
+                        mon.try_add(1).unwrap();
+                    }
+                });
+            }
+        });
+        assert_eq!(mon.completed().as_ref(), &NumericWork::new(1000u64));
+        mon.close().unwrap();
+    }
+}
@@ -3,16 +3,22 @@ use std::{
     fmt::{Debug, Display},
 };
 
-use crate::{prelude::ChildMonitor, work::Work, CloseError};
+#[cfg(test)]
+use crate::work::numeric::NumericWork;
+use crate::{error::WorkError, work::Work};
 
-use super::{ProgressMonitor, ProgressMonitorDivision};
+use super::{
+    cancel::CancellationToken,
+    clock::Clock,
+    config::{handle_drop, MonitorConfig},
+    state::MonitorState,
+    subscription::Subscription,
+    CloseOutcome, ProgressMonitor, ProgressMonitorDivision,
+};
 
 pub struct CallbackProgressMonitor<'n, W: Work, C: Fn(&W, &W)> {
-    name: Cow<'n, str>,
-    work: W,
-    work_done: W,
+    state: MonitorState<'n, W>,
     callback: C,
-    closed: Option<Result<(), CloseError>>,
 }
 
 impl<'n, W, C> Debug for CallbackProgressMonitor<'n, W, C>
@@ -22,9 +28,9 @@ where
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("CallbackProgressMonitor")
-            .field("name", &self.name)
-            .field("work", &self.work)
-            .field("work_done", &self.work_done)
+            .field("name", &self.state.name)
+            .field("work", &self.state.work)
+            .field("work_done", &self.state.work_done)
             .finish()
     }
 }
@@ -36,13 +42,41 @@ where
 {
     pub fn new<N: Into<Cow<'n, str>>, A: Into<W>>(name: N, work: A, callback: C) -> Self {
         Self {
-            name: name.into(),
-            work: work.into(),
-            work_done: W::zero(),
+            state: MonitorState::new(name.into(), work.into()),
+            callback,
+        }
+    }
+
+    /// A monitor for work with an unknown total, e.g. consuming a stream.
+    /// Work is counted without a limit and the callback is handed a total of zero. Closing always completes the monitor.
+    /// Call `set_total` once the total becomes known.
+    pub fn indeterminate<N: Into<Cow<'n, str>>>(name: N, callback: C) -> Self {
+        Self {
+            state: MonitorState::new(name.into(), W::zero()).indeterminate(),
             callback,
-            closed: None,
         }
     }
+
+    pub fn with_config(mut self, config: MonitorConfig) -> Self {
+        self.state.config = config;
+        self
+    }
+
+    /// Observe the progress of this monitor from somewhere else, e.g. another thread.
+    pub fn subscribe(&mut self) -> Subscription<W> {
+        self.state.subscribe()
+    }
+}
+
+#[cfg(test)]
+type NumericCallback = fn(&NumericWork<u64>, &NumericWork<u64>);
+
+#[cfg(test)]
+impl<'n> CallbackProgressMonitor<'n, NumericWork<u64>, NumericCallback> {
+    /// A numeric monitor ignoring its progress, for tests.
+    pub(crate) fn silent<N: Into<Cow<'n, str>>>(name: N, total: u64) -> Self {
+        Self::new(name, total, |_, _| {})
+    }
 }
 
 impl<'n, W, C> ProgressMonitor<W> for CallbackProgressMonitor<'n, W, C>
@@ -50,53 +84,82 @@ where
     W: Work,
     C: Fn(&W, &W),
 {
-    fn worked<A: Into<W>>(&mut self, amount: A) {
-        let amount: W = amount.into();
-        let now: W = (self.work_done.clone() + amount.clone()).expect("Addition to work"); // TODO: Handle error!?
-        if now > self.work {
-            // TODO: Control overshoot behavior through monitor configuration.
-            tracing::warn!(
-                work = ?self.work,
-                work_done = ?self.work_done,
-                new_work_done = ?amount,
-                would_become = ?now,
-                "Detected overshoot. Try to only submit work left open. Ignoring additional work."
-            );
-            self.work_done = self.work.clone();
-        } else {
-            self.work_done = now;
-        }
-        (self.callback)(&self.work, &self.work_done);
+    fn name(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.state.name)
+    }
+
+    fn try_worked<A: Into<W>>(&mut self, amount: A) -> Result<(), WorkError> {
+        self.state.try_worked(amount.into())?;
+        (self.callback)(&self.state.work, &self.state.work_done);
+        Ok(())
+    }
+
+    fn try_unworked<A: Into<W>>(&mut self, amount: A) -> Result<(), WorkError> {
+        self.state.try_unworked(amount.into())?;
+        (self.callback)(&self.state.work, &self.state.work_done);
+        Ok(())
     }
 
     /// Get the total amount of work.
-    fn total(&self) -> &W {
-        &self.work
+    fn total(&self) -> Cow<'_, W> {
+        Cow::Borrowed(&self.state.work)
+    }
+
+    fn try_set_total<A: Into<W>>(&mut self, total: A) -> Result<(), WorkError> {
+        self.state.try_set_total(total.into())?;
+        (self.callback)(&self.state.work, &self.state.work_done);
+        Ok(())
     }
 
     /// Get the amount of work completed.
-    fn completed(&self) -> &W {
-        &self.work_done
+    fn completed(&self) -> Cow<'_, W> {
+        Cow::Borrowed(&self.state.work_done)
     }
 
     /// Get the amount of work remaining.
-    fn remaining(&self) -> Cow<W> {
-        Cow::Owned(self.work.clone() - self.work_done.clone())
-    }
-
-    fn close(&mut self) -> Result<(), crate::CloseError> {
-        let work_left = self.remaining();
-        let result = if work_left.as_ref() == &W::zero() {
-            Ok(())
-        } else {
-            Err(crate::CloseError {
-                msg: format!(
-                    "Must not drop progress monitor {self:#?} when work left is {work_left}."
-                ),
-            })
-        };
-        self.closed = Some(result.clone());
-        result
+    fn remaining(&self) -> Cow<'_, W> {
+        Cow::Owned(self.state.remaining())
+    }
+
+    fn close(&mut self) -> Result<CloseOutcome, crate::CloseError> {
+        self.state.close()
+    }
+
+    fn pause(&mut self) {
+        self.state.pause()
+    }
+
+    fn resume(&mut self) {
+        self.state.resume()
+    }
+
+    fn clock(&self) -> Clock {
+        self.state.clock
+    }
+
+    fn skip<R: Into<String>>(&mut self, reason: R) -> CloseOutcome {
+        self.state.skip(reason.into())
+    }
+
+    fn is_indeterminate(&self) -> bool {
+        self.state.indeterminate
+    }
+
+    fn cancellation_token(&self) -> CancellationToken {
+        self.state.token.clone()
+    }
+
+    fn config(&self) -> MonitorConfig {
+        self.state.config
+    }
+
+    fn path(&self) -> Vec<String> {
+        self.state.path.clone()
+    }
+
+    #[cfg(feature = "registry")]
+    fn registry_id(&self) -> Option<crate::registry::MonitorId> {
+        self.state.registration.id()
     }
 }
 
@@ -109,38 +172,20 @@ where
     A2: Into<W>,
     C: Fn(&W, &W),
 {
-    fn new_child(
-        &'p mut self,
-        name: N,
-        parent_work: A1,
-        child_work: A2,
-    ) -> ChildMonitor<'n, 'p, W, Self> {
-        let parent_work: W = parent_work.into();
-        let total_child_work: W = child_work.into();
-
-        // TODO: As Result?
-        assert!(&parent_work <= self.remaining().as_ref());
-
-        ChildMonitor::new(name.into(), self, parent_work, total_child_work)
-    }
 }
 
 impl<'n, W: Work, C: Fn(&W, &W)> Display for CallbackProgressMonitor<'n, W, C> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_fmt(format_args!("{}/{}", self.work_done, self.work))
+        match self.state.indeterminate {
+            true => f.write_fmt(format_args!("{}/?", self.state.work_done)),
+            false => f.write_fmt(format_args!("{}/{}", self.state.work_done, self.state.work)),
+        }
     }
 }
 
 impl<'n, W: Work, C: Fn(&W, &W)> Drop for CallbackProgressMonitor<'n, W, C> {
     fn drop(&mut self) {
-        match &self.closed {
-            Some(result) => {
-                assert!(result.is_ok());
-            }
-            None => {
-                tracing::warn!("close() was not called on {self:?}!");
-                self.close().expect("Successful close");
-            }
-        }
+        let closed = self.state.closed.clone();
+        handle_drop(self, closed);
     }
 }
@@ -0,0 +1,76 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Signals that the work tracked by a monitor should stop. Obtained through `ProgressMonitor::cancellation_token`.
+///
+/// Tokens form a tree, mirroring the tree of monitors. A token is cancelled if it or any of its ancestors was cancelled.
+/// Tokens are cheap to clone and can be handed to the code doing the work, e.g. on other threads.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    parent: Option<CancellationToken>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a token which is cancelled whenever this token is cancelled, but which can be cancelled on its own.
+    pub fn child_token(&self) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                cancelled: AtomicBool::new(false),
+                parent: Some(self.clone()),
+            }),
+        }
+    }
+
+    /// Cancel this token and all tokens derived from it.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::Release);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        let mut token = Some(self);
+        while let Some(current) = token {
+            if current.inner.cancelled.load(Ordering::Acquire) {
+                return true;
+            }
+            token = current.inner.parent.as_ref();
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+
+    #[test]
+    fn cancellation_spreads_to_descendants() {
+        let mut root = CallbackProgressMonitor::silent("root", 10);
+        let token = root.cancellation_token();
+        {
+            let mut child = root.new_child("child", 10u64, 5u64);
+            let mut grandchild = child.new_child("grandchild", 5u64, 2u64);
+            grandchild.worked(1u64);
+            let worker_token = grandchild.cancellation_token();
+            assert!(!worker_token.is_cancelled());
+
+            token.cancel();
+            assert!(worker_token.is_cancelled());
+            assert!(grandchild.is_cancelled());
+            assert_eq!(grandchild.close(), Ok(CloseOutcome::Cancelled));
+        }
+        assert!(root.is_cancelled());
+        assert_eq!(root.close(), Ok(CloseOutcome::Cancelled));
+    }
+}
@@ -0,0 +1,110 @@
+use std::time::{Duration, Instant};
+
+/// Tracks the time a monitor was active and the time it was paused. See `ProgressMonitor::pause`.
+///
+/// Time only counts up to the moment the monitor was closed.
+/// Rates or estimates should be based on `active_time`, which leaves out the time the monitor was paused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Clock {
+    started: Instant,
+    stopped: Option<Instant>,
+    /// The time paused, not including the current pause.
+    paused: Duration,
+    paused_since: Option<Instant>,
+}
+
+impl Clock {
+    pub(crate) fn start() -> Self {
+        Self {
+            started: Instant::now(),
+            stopped: None,
+            paused: Duration::ZERO,
+            paused_since: None,
+        }
+    }
+
+    fn now(&self) -> Instant {
+        self.stopped.unwrap_or_else(Instant::now)
+    }
+
+    /// Returns whether the clock was running.
+    pub(crate) fn pause(&mut self) -> bool {
+        if self.paused_since.is_some() || self.stopped.is_some() {
+            return false;
+        }
+        self.paused_since = Some(Instant::now());
+        true
+    }
+
+    /// Returns whether the clock was paused.
+    pub(crate) fn resume(&mut self) -> bool {
+        if self.stopped.is_some() {
+            return false;
+        }
+        match self.paused_since.take() {
+            Some(since) => {
+                self.paused += since.elapsed();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stops counting time, e.g. when the monitor is closed. A pause in progress ends.
+    pub(crate) fn stop(&mut self) {
+        if self.stopped.is_none() {
+            self.resume();
+            self.stopped = Some(Instant::now());
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_since.is_some()
+    }
+
+    /// The time since the monitor was created.
+    pub fn elapsed(&self) -> Duration {
+        self.now().saturating_duration_since(self.started)
+    }
+
+    /// The time the monitor was paused, including the current pause.
+    pub fn paused_time(&self) -> Duration {
+        match self.paused_since {
+            Some(since) => self.paused + self.now().saturating_duration_since(since),
+            None => self.paused,
+        }
+    }
+
+    /// The time the monitor was not paused.
+    pub fn active_time(&self) -> Duration {
+        self.elapsed().saturating_sub(self.paused_time())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{thread, time::Duration};
+
+    use crate::prelude::*;
+
+    #[test]
+    fn paused_time_is_not_active() {
+        let mut mon = CallbackProgressMonitor::silent("root", 10);
+        let mut subscription = mon.subscribe();
+        mon.worked(5u64);
+        mon.pause();
+        assert!(mon.is_paused());
+        assert!(subscription.latest().paused);
+        thread::sleep(Duration::from_millis(50));
+        mon.resume();
+        assert!(!subscription.latest().paused);
+
+        let clock = mon.clock();
+        assert!(clock.paused_time() >= Duration::from_millis(50));
+        assert!(clock.active_time() < clock.elapsed() - Duration::from_millis(40));
+
+        mon.worked(5u64);
+        mon.close().unwrap();
+        assert_eq!(mon.clock().elapsed(), mon.clock().elapsed());
+    }
+}
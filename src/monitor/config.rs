@@ -0,0 +1,301 @@
+use std::fmt::Debug;
+
+use crate::{
+    error::{CloseError, MonitorContext, NewChildError, WorkError},
+    work::Work,
+};
+
+use super::{CloseOutcome, ProgressMonitor};
+
+/// Settings controlling the behavior of a monitor. Children inherit the configuration of their parent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MonitorConfig {
+    /// How `ProgressMonitor::worked` handles work which was rejected.
+    pub on_invalid_work: InvalidWorkPolicy,
+    /// How work exceeding the total amount of work is handled.
+    pub on_overshoot: OvershootPolicy,
+    /// How children requesting more work than their parent has remaining are handled.
+    pub on_excess_share: ExcessSharePolicy,
+    /// How monitors dropped without being closed successfully are handled.
+    pub on_drop: DropPolicy,
+}
+
+/// How `ProgressMonitor::worked` handles work rejected by `ProgressMonitor::try_worked`.
+/// Rejected work is never applied, not even partially.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InvalidWorkPolicy {
+    /// Panic. Useful in tests.
+    Panic,
+    /// Log a warning and ignore the work.
+    #[default]
+    Warn,
+    /// Ignore the work silently.
+    Ignore,
+}
+
+/// How a monitor handles work exceeding its total amount of work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OvershootPolicy {
+    /// Accept the work left open and ignore the rest.
+    ClampSilently,
+    /// Accept the work left open and ignore the rest, logging a warning.
+    #[default]
+    ClampAndWarn,
+    /// Reject the work as a whole with `WorkError::Overshoot`.
+    Error,
+    /// Panic. Useful in tests.
+    Panic,
+    /// Accept all work, growing the total amount of work accordingly.
+    GrowTotal,
+}
+
+/// How a monitor handles children requesting more of its work than it has remaining.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExcessSharePolicy {
+    /// `try_new_child` fails with a `NewChildError`, `new_child` panics.
+    #[default]
+    Reject,
+    /// Hand the child whatever work remains.
+    Clamp,
+}
+
+impl ExcessSharePolicy {
+    /// Decide on the work handed to children requesting `shares` of `parent`, while only `available` is remaining.
+    pub(crate) fn share<W: Work>(
+        self,
+        parent: impl FnOnce() -> MonitorContext,
+        shares: &[&W],
+        available: W,
+    ) -> Result<W, NewChildError> {
+        let requested = shares
+            .iter()
+            .try_fold(W::zero(), |acc, share| acc + (*share).clone());
+        match requested {
+            Ok(requested) if requested <= available => Ok(requested),
+            _ if self == ExcessSharePolicy::Clamp => Ok(available),
+            // A sum which is not even representable exceeds the work available as well.
+            _ => Err(NewChildError {
+                parent: Box::new(parent()),
+                available: available.to_string(),
+                requested: shares
+                    .iter()
+                    .map(|share| share.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" + "),
+            }),
+        }
+    }
+}
+
+/// How a monitor handles being dropped without being closed successfully.
+///
+/// A monitor dropped without being closed, but with all its work done or cancelled, is always closed successfully.
+/// Only `CallbackProgressMonitor`, `ChildMonitor` and `AtomicProgressMonitor` follow this policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DropPolicy {
+    /// Submit the work remaining and close the monitor successfully.
+    AutoComplete,
+    /// Close the monitor, recording the failure for subscribers, and log an error.
+    MarkFailed,
+    /// Log a warning.
+    Warn,
+    /// Panic if `close()` was never called, unless the thread is already panicking.
+    /// The monitor is marked as failed otherwise. Its caller was handed the error of a failed `close()` already.
+    #[default]
+    Panic,
+}
+
+/// Handles `monitor` being dropped, according to its `DropPolicy`. `closed` is the result of closing it, if it was closed.
+pub(crate) fn handle_drop<W, M>(monitor: &mut M, closed: Option<Result<CloseOutcome, CloseError>>)
+where
+    W: Work,
+    M: ProgressMonitor<W>,
+{
+    // Whether the caller was handed the error of closing the monitor already.
+    let close_attempted = matches!(closed, Some(Err(_)));
+    let closed = match closed {
+        Some(Ok(_)) => return,
+        Some(Err(err)) => Some(err),
+        // Stopping early is expected from cancelled monitors.
+        None if monitor.is_cancelled() => {
+            let _ = monitor.close();
+            return;
+        }
+        None if monitor.remaining().as_ref() == &W::zero() => {
+            tracing::warn!("close() was not called on {monitor:?}!");
+            match monitor.close() {
+                Ok(_) => return,
+                Err(err) => Some(err),
+            }
+        }
+        None => None,
+    };
+    let policy = monitor.config().on_drop;
+    let panicking = std::thread::panicking();
+    match policy {
+        DropPolicy::AutoComplete => {
+            let remaining = monitor.remaining().into_owned();
+            let result = monitor
+                .try_worked(remaining)
+                .map_err(|err| err.to_string())
+                .and_then(|()| monitor.close().map_err(|err| err.to_string()));
+            if let Err(err) = result {
+                tracing::error!("Could not complete {monitor:?} on drop. Reason: {err}");
+            }
+        }
+        DropPolicy::MarkFailed | DropPolicy::Panic => {
+            let err = match closed {
+                Some(err) => err,
+                None => match monitor.close() {
+                    Ok(_) => return,
+                    Err(err) => err,
+                },
+            };
+            if policy == DropPolicy::Panic && !close_attempted && !panicking {
+                panic!("Monitor was not successfully closed. Reason: {err}");
+            }
+            tracing::error!("Monitor was not successfully closed. Reason: {err}");
+        }
+        DropPolicy::Warn => match closed {
+            Some(err) => tracing::warn!("Monitor was not successfully closed. Reason: {err}"),
+            None => tracing::warn!("close() was not called on {monitor:?}, which has work left!"),
+        },
+    }
+}
+
+impl InvalidWorkPolicy {
+    pub(crate) fn handle<M: Debug + ?Sized>(self, err: &WorkError, monitor: &M) {
+        match self {
+            InvalidWorkPolicy::Panic => panic!("Invalid work submitted to {monitor:?}: {err}"),
+            InvalidWorkPolicy::Warn => {
+                tracing::warn!("Ignoring invalid work submitted to {monitor:?}. Reason: {err}")
+            }
+            InvalidWorkPolicy::Ignore => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+
+    #[test]
+    fn children_inherit_overshoot_policy() {
+        let mut root = CallbackProgressMonitor::silent("root", 10).with_config(MonitorConfig {
+            on_overshoot: OvershootPolicy::Error,
+            ..Default::default()
+        });
+        {
+            let mut child = root.new_child("child", 5u64, 10u64);
+            child.try_worked(8u64).unwrap();
+            match child.try_worked(3u64) {
+                Err(WorkError::Overshoot { monitor, submitted }) => {
+                    assert_eq!(monitor.path, ["root", "child"]);
+                    assert_eq!(monitor.remaining, "2");
+                    assert_eq!(submitted, "3");
+                }
+                other => panic!("Expected overshoot, got {other:?}"),
+            }
+            assert_eq!(child.completed().as_ref(), &NumericWork::new(8u64));
+
+            let mut child = child.with_config(MonitorConfig {
+                on_overshoot: OvershootPolicy::GrowTotal,
+                ..Default::default()
+            });
+            child.try_worked(3u64).unwrap();
+            assert_eq!(child.total().as_ref(), &NumericWork::new(11u64));
+            child.close().unwrap();
+        }
+        assert_eq!(root.completed().as_ref(), &NumericWork::new(5u64));
+        root.worked(5u64);
+        root.close().unwrap();
+    }
+
+    #[test]
+    fn excess_share_is_rejected_or_clamped() {
+        let mut root = CallbackProgressMonitor::silent("root", 10);
+        root.worked(4u64);
+        let err = root.try_new_child("child", 8u64, 1u64).unwrap_err();
+        assert_eq!(err.parent.name, "root");
+        assert_eq!(err.available, "6");
+        assert_eq!(err.requested, "8");
+
+        let mut root = root.with_config(MonitorConfig {
+            on_excess_share: ExcessSharePolicy::Clamp,
+            ..Default::default()
+        });
+        {
+            let mut child = root.try_new_child("child", 8u64, 1u64).unwrap();
+            child.worked(1u64);
+            child.close().unwrap();
+        }
+        root.close().unwrap();
+    }
+
+    #[test]
+    fn drop_policy_applies_to_children_and_never_aborts() {
+        let mut root = CallbackProgressMonitor::silent("root", 10).with_config(MonitorConfig {
+            on_drop: DropPolicy::AutoComplete,
+            ..Default::default()
+        });
+        {
+            let mut child = root.new_child("child", 4u64, 10u64);
+            child.worked(3u64);
+        }
+        assert_eq!(root.completed().as_ref(), &NumericWork::new(4u64));
+
+        // Dropping an incomplete monitor while unwinding must not panic again, which would abort.
+        let result = std::panic::catch_unwind(|| {
+            let mut mon = CallbackProgressMonitor::silent("failing", 10);
+            mon.worked(1u64);
+            panic!("step failed");
+        });
+        assert!(result.is_err());
+
+        root.worked(6u64);
+        root.close().unwrap();
+    }
+
+    #[test]
+    fn failed_close_is_only_logged_on_drop() {
+        let mut mon = CallbackProgressMonitor::silent("root", 10);
+        let mut subscription = mon.subscribe();
+        mon.worked(1u64);
+        assert!(mon.close().is_err());
+        drop(mon);
+        assert!(matches!(subscription.latest().closed, Some(Err(_))));
+
+        // Dropping a monitor on which close() was never called still panics.
+        let result = std::panic::catch_unwind(|| {
+            let mut mon = CallbackProgressMonitor::silent("root", 10);
+            mon.worked(1u64);
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn mark_failed_closes_and_warn_leaves_monitor_open() {
+        let mut mon = CallbackProgressMonitor::silent("root", 10).with_config(MonitorConfig {
+            on_drop: DropPolicy::MarkFailed,
+            ..Default::default()
+        });
+        let mut subscription = mon.subscribe();
+        mon.worked(1u64);
+        drop(mon);
+        assert!(matches!(
+            subscription.latest().closed,
+            Some(Err(CloseError::Incomplete { .. }))
+        ));
+
+        let mut mon = CallbackProgressMonitor::silent("root", 10).with_config(MonitorConfig {
+            on_drop: DropPolicy::Warn,
+            ..Default::default()
+        });
+        let mut subscription = mon.subscribe();
+        mon.worked(1u64);
+        drop(mon);
+        let snapshot = subscription.latest();
+        assert_eq!(snapshot.closed, None);
+        assert_eq!(snapshot.completed, NumericWork::new(1u64));
+    }
+}
@@ -0,0 +1,373 @@
+use std::{
+    borrow::Cow,
+    fmt::{Debug, Display},
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+};
+
+use crate::{error::WorkError, work::Work, CloseError};
+
+use super::{
+    cancel::CancellationToken,
+    clock::Clock,
+    config::MonitorConfig,
+    state::{MonitorState, ParentShare},
+    subscription::Subscription,
+    CloseOutcome, ProgressMonitor, ProgressMonitorDivision,
+};
+
+/// Messages sent from a detached child to the receiver held by its parent.
+#[derive(Debug)]
+enum DetachedEvent<W> {
+    /// Parent work to be submitted, already scaled to the parents scale.
+    Worked(W),
+    /// Parent work to be taken back, already scaled to the parents scale.
+    Unworked(W),
+    Closed(Result<CloseOutcome, CloseError>),
+}
+
+/// A child monitor which owns all its state and is `'static`.
+/// In contrast to `ChildMonitor`, it does not borrow its parent and can therefore be moved to other threads,
+/// stored in structs or returned from functions.
+///
+/// All progress is scaled to the parents work (see `ChildMonitor`) and sent to a `DetachedChildReceiver`
+/// over a channel. The receiver must be used to forward the progress into the actual parent monitor.
+#[derive(Debug)]
+pub struct DetachedChildMonitor<W: Work> {
+    state: MonitorState<'static, W>,
+    share: ParentShare<W>,
+    sender: Sender<DetachedEvent<W>>,
+}
+
+/// The parents end of a `DetachedChildMonitor`. Forwards the progress made in the child into a parent monitor.
+#[derive(Debug)]
+pub struct DetachedChildReceiver<W: Work> {
+    name: Cow<'static, str>,
+    receiver: Receiver<DetachedEvent<W>>,
+    closed: Option<Result<CloseOutcome, CloseError>>,
+}
+
+/// The state of a `DetachedChildMonitor`, as seen from its receiver.
+#[derive(Debug, Clone)]
+pub enum DetachedChildStatus {
+    /// The child is still alive. More work might arrive.
+    Connected,
+    /// The child is gone. Contains the result of closing it.
+    /// A child dropped without being closed (successfully) is reported with an error.
+    Disconnected(Result<CloseOutcome, CloseError>),
+}
+
+impl<W: Work> DetachedChildMonitor<W> {
+    /// Create a detached child, responsible for `parent_work` of its parents work, using its own scale of `child_work`.
+    /// The returned receiver must be used to forward the progress into the parent.
+    ///
+    /// The child knows nothing about its parent: `parent_work` is not checked and neither configuration,
+    /// path nor cancellation are inherited. Prefer `ProgressMonitorDivision::new_detached_child`.
+    pub fn new<N, A1, A2>(
+        name: N,
+        parent_work: A1,
+        child_work: A2,
+    ) -> (Self, DetachedChildReceiver<W>)
+    where
+        N: Into<Cow<'static, str>>,
+        A1: Into<W>,
+        A2: Into<W>,
+    {
+        Self::from_state(
+            MonitorState::new(name.into(), child_work.into()),
+            parent_work.into(),
+        )
+    }
+
+    /// Create a detached child of `parent`, inheriting its configuration, path and cancellation.
+    pub(crate) fn child_of<P: ProgressMonitor<W>>(
+        name: Cow<'static, str>,
+        parent: &P,
+        parent_work: W,
+        child_work: W,
+    ) -> (Self, DetachedChildReceiver<W>) {
+        Self::from_state(
+            MonitorState::new(name, child_work).child_of(parent),
+            parent_work,
+        )
+    }
+
+    fn from_state(
+        state: MonitorState<'static, W>,
+        parent_work: W,
+    ) -> (Self, DetachedChildReceiver<W>) {
+        let (sender, receiver) = mpsc::channel();
+        let receiver = DetachedChildReceiver {
+            name: state.name.clone(),
+            receiver,
+            closed: None,
+        };
+        let child = Self {
+            state,
+            share: ParentShare::new(parent_work),
+            sender,
+        };
+        (child, receiver)
+    }
+
+    pub fn name(&self) -> Cow<'static, str> {
+        self.state.name.clone()
+    }
+
+    pub fn with_config(mut self, config: MonitorConfig) -> Self {
+        self.state.config = config;
+        self
+    }
+
+    /// Cancel this child whenever `token` is cancelled, e.g. the `cancellation_token()` of its parent.
+    pub fn with_cancellation_token(mut self, token: &CancellationToken) -> Self {
+        self.state.token = token.child_token();
+        self
+    }
+
+    /// Observe the progress of this monitor from somewhere else, e.g. another thread.
+    pub fn subscribe(&mut self) -> Subscription<W> {
+        self.state.subscribe()
+    }
+
+    fn send(&self, event: DetachedEvent<W>) {
+        if self.sender.send(event).is_err() {
+            tracing::debug!(
+                name = %self.state.name,
+                "Receiver of detached child monitor is gone. Dropping event."
+            );
+        }
+    }
+}
+
+impl<W: Work> ProgressMonitor<W> for DetachedChildMonitor<W> {
+    fn name(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.state.name)
+    }
+
+    fn try_worked<A: Into<W>>(&mut self, amount_of_work: A) -> Result<(), WorkError> {
+        let checkpoint = self.state.checkpoint();
+        self.state.try_worked(amount_of_work.into())?;
+
+        let (state, mut parent_worked) = (&self.state, W::zero());
+        let shared = self.share.atomically(|share| {
+            parent_worked = share.advance(state)?;
+            Ok(())
+        });
+        if let Err(err) = shared {
+            self.state.restore(checkpoint);
+            return Err(err);
+        }
+        self.send(DetachedEvent::Worked(parent_worked));
+        Ok(())
+    }
+
+    fn try_unworked<A: Into<W>>(&mut self, amount_of_work: A) -> Result<(), WorkError> {
+        let checkpoint = self.state.checkpoint();
+        self.state.try_unworked(amount_of_work.into())?;
+
+        let (state, mut parent_taken) = (&self.state, W::zero());
+        let shared = self.share.atomically(|share| {
+            parent_taken = share.retreat(state)?;
+            Ok(())
+        });
+        if let Err(err) = shared {
+            self.state.restore(checkpoint);
+            return Err(err);
+        }
+        self.send(DetachedEvent::Unworked(parent_taken));
+        Ok(())
+    }
+
+    fn total(&self) -> Cow<'_, W> {
+        Cow::Borrowed(&self.state.work)
+    }
+
+    fn try_set_total<A: Into<W>>(&mut self, total: A) -> Result<(), WorkError> {
+        let checkpoint = self.state.checkpoint();
+        self.state.try_set_total(total.into())?;
+
+        let (state, mut parent_worked) = (&self.state, None);
+        let shared = self.share.atomically(|share| {
+            parent_worked = share.rescale(state)?;
+            Ok(())
+        });
+        if let Err(err) = shared {
+            self.state.restore(checkpoint);
+            return Err(err);
+        }
+        if let Some(parent_worked) = parent_worked {
+            self.send(DetachedEvent::Worked(parent_worked));
+        }
+        Ok(())
+    }
+
+    fn completed(&self) -> Cow<'_, W> {
+        Cow::Borrowed(&self.state.work_done)
+    }
+
+    fn remaining(&self) -> Cow<'_, W> {
+        Cow::Owned(self.state.remaining())
+    }
+
+    fn close(&mut self) -> Result<CloseOutcome, CloseError> {
+        let result = self.state.close();
+        self.send(DetachedEvent::Closed(result.clone()));
+        result
+    }
+
+    fn pause(&mut self) {
+        self.state.pause()
+    }
+
+    fn resume(&mut self) {
+        self.state.resume()
+    }
+
+    fn clock(&self) -> Clock {
+        self.state.clock
+    }
+
+    fn skip<R: Into<String>>(&mut self, reason: R) -> CloseOutcome {
+        if self.state.is_closed() {
+            return self.state.skip(reason.into());
+        }
+        let parent_worked = self.share.rest();
+        if parent_worked != W::zero() {
+            self.send(DetachedEvent::Worked(parent_worked));
+        }
+        let outcome = self.state.skip(reason.into());
+        self.send(DetachedEvent::Closed(Ok(outcome.clone())));
+        outcome
+    }
+
+    fn cancellation_token(&self) -> CancellationToken {
+        self.state.token.clone()
+    }
+
+    fn config(&self) -> MonitorConfig {
+        self.state.config
+    }
+
+    fn path(&self) -> Vec<String> {
+        self.state.path.clone()
+    }
+
+    #[cfg(feature = "registry")]
+    fn registry_id(&self) -> Option<crate::registry::MonitorId> {
+        self.state.registration.id()
+    }
+}
+
+impl<'p, 'n, N, W, A1, A2> ProgressMonitorDivision<'p, 'n, N, W, A1, A2> for DetachedChildMonitor<W>
+where
+    N: Into<Cow<'n, str>>,
+    W: Work,
+    A1: Into<W>,
+    A2: Into<W>,
+{
+}
+
+impl<W: Work> Display for DetachedChildMonitor<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}/{}", self.state.work_done, self.state.work))
+    }
+}
+
+impl<W: Work> Drop for DetachedChildMonitor<W> {
+    fn drop(&mut self) {
+        if self.state.closed.is_none() {
+            // The receiver reports the outcome. See `DetachedChildReceiver`.
+            tracing::warn!("close() was not called on {self:?}!");
+            let _ = self.close();
+        }
+    }
+}
+
+impl<W: Work> DetachedChildReceiver<W> {
+    pub fn name(&self) -> Cow<'static, str> {
+        self.name.clone()
+    }
+
+    /// Forward all progress which arrived so far into `parent`, without blocking.
+    pub fn try_forward<P: ProgressMonitor<W>>(&mut self, parent: &mut P) -> DetachedChildStatus {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(event) => self.handle(event, parent),
+                Err(TryRecvError::Empty) => return DetachedChildStatus::Connected,
+                Err(TryRecvError::Disconnected) => return self.disconnected(),
+            }
+        }
+    }
+
+    /// Forward all progress into `parent`, blocking until the child is gone.
+    /// Returns the result of closing the child.
+    pub fn forward<P: ProgressMonitor<W>>(
+        mut self,
+        parent: &mut P,
+    ) -> Result<CloseOutcome, CloseError> {
+        while let Ok(event) = self.receiver.recv() {
+            self.handle(event, parent);
+        }
+        match self.disconnected() {
+            DetachedChildStatus::Connected => unreachable!("channel is disconnected"),
+            DetachedChildStatus::Disconnected(result) => result,
+        }
+    }
+
+    fn handle<P: ProgressMonitor<W>>(&mut self, event: DetachedEvent<W>, parent: &mut P) {
+        match event {
+            DetachedEvent::Worked(parent_worked) => parent.worked(parent_worked),
+            DetachedEvent::Unworked(parent_taken) => parent.unworked(parent_taken),
+            DetachedEvent::Closed(result) => self.closed = Some(result),
+        }
+    }
+
+    fn disconnected(&self) -> DetachedChildStatus {
+        let result = match &self.closed {
+            Some(result) => result.clone(),
+            None => Err(CloseError::Disconnected {
+                name: self.name.to_string(),
+            }),
+        };
+        if let Err(err) = &result {
+            tracing::error!(
+                "Detached child monitor was not successfully closed. Reason: {}",
+                err
+            );
+        }
+        DetachedChildStatus::Disconnected(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn detached_child_reports_through_receiver() {
+        let mut root = CallbackProgressMonitor::silent("root", 100);
+
+        let (mut child, receiver) = root.new_detached_child("worker", 60u64, 3u64);
+        assert_eq!(child.path(), ["root", "worker"]);
+        let handle = thread::spawn(move || {
+            child.worked(1u64);
+            child.worked(2u64);
+            child.close().unwrap();
+        });
+        receiver.forward(&mut root).unwrap();
+        handle.join().unwrap();
+        assert_eq!(root.completed().as_ref(), &NumericWork::new(60u64));
+
+        let (child, receiver) =
+            DetachedChildMonitor::<NumericWork<u64>>::new("vanished", 40u64, 1u64);
+        drop(child);
+        assert!(receiver.forward(&mut root).is_err());
+
+        assert!(root.try_new_detached_child("excess", 50u64, 1u64).is_err());
+
+        root.worked(40u64);
+        root.close().unwrap();
+    }
+}
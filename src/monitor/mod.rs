@@ -1,24 +1,263 @@
-use std::{borrow::Cow, fmt::Debug, fmt::Display};
+use std::{borrow::Cow, cmp::Ordering, fmt::Debug, fmt::Display};
 
-use crate::{prelude::CloseError, work::Work};
+use crate::{
+    error::{MonitorContext, NewChildError, WorkError},
+    prelude::CloseError,
+    work::Work,
+};
 
-use self::sub::ChildMonitor;
+use self::{
+    cancel::CancellationToken,
+    clock::Clock,
+    config::MonitorConfig,
+    detached::{DetachedChildMonitor, DetachedChildReceiver},
+    shared::SharedProgressMonitor,
+    sub::ChildMonitor,
+};
 
+#[cfg(feature = "async")]
+pub mod asynchronous;
+pub mod atomic;
 pub mod callback;
+pub mod cancel;
+pub mod clock;
+pub mod config;
+pub mod detached;
+pub mod shared;
+pub(crate) mod state;
 pub mod sub;
+pub mod subscription;
+
+/// How a monitor was closed successfully.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CloseOutcome {
+    /// All work was done.
+    Completed,
+    /// The monitor was cancelled, work may be left.
+    Cancelled,
+    /// The work of the monitor became unnecessary. See `ProgressMonitor::skip`.
+    Skipped { reason: String },
+}
 
 /// A ProgressMonitor tracks an amount of work which must be completed.
 pub trait ProgressMonitor<W: Work>: Debug + Display {
-    fn worked<A: Into<W>>(&mut self, amount_of_work: A);
+    /// The name of this monitor. For debug purposes only.
+    fn name(&self) -> Cow<'_, str>;
+
+    /// The names of all monitors from the root down to this monitor, including its own name.
+    fn path(&self) -> Vec<String> {
+        vec![self.name().into_owned()]
+    }
+
+    /// Describes this monitor, e.g. for error reporting.
+    fn context(&self) -> MonitorContext {
+        MonitorContext {
+            name: self.name().into_owned(),
+            path: self.path(),
+            total: self.total().to_string(),
+            completed: self.completed().to_string(),
+            remaining: self.remaining().to_string(),
+        }
+    }
+
+    /// Submit work. Work which is invalid, e.g. a set item which was already done, is rejected and not applied.
+    fn try_worked<A: Into<W>>(&mut self, amount_of_work: A) -> Result<(), WorkError>;
+
+    /// Submit work. Invalid work is handled according to the `InvalidWorkPolicy` of this monitor. See `try_worked`.
+    fn worked<A: Into<W>>(&mut self, amount_of_work: A) {
+        if let Err(err) = self.try_worked(amount_of_work) {
+            self.config().on_invalid_work.handle(&err, self);
+        }
+    }
+
+    /// Take back work which was completed, e.g. when a step failed and is retried. Subscribers and callbacks see the monitor move backwards.
+    /// A child takes back the corresponding parent work from its parent.
+    /// Fails if the work was not completed, in which case nothing is taken back.
+    fn try_unworked<A: Into<W>>(&mut self, amount_of_work: A) -> Result<(), WorkError>;
+
+    /// Take back work which was completed. Invalid work is handled according to the `InvalidWorkPolicy` of this monitor.
+    /// See `try_unworked`.
+    fn unworked<A: Into<W>>(&mut self, amount_of_work: A) {
+        if let Err(err) = self.try_unworked(amount_of_work) {
+            self.config().on_invalid_work.handle(&err, self);
+        }
+    }
+
+    /// Take back all work completed.
+    fn reset(&mut self) {
+        let completed = self.completed().into_owned();
+        self.unworked(completed);
+    }
+
+    /// Run `attempt`, taking back all work it submitted to this monitor if it fails.
+    fn attempt<T, E, F>(&mut self, attempt: F) -> Result<T, E>
+    where
+        Self: Sized,
+        F: FnOnce(&mut Self) -> Result<T, E>,
+    {
+        let before = self.completed().into_owned();
+        let result = attempt(self);
+        if result.is_err() {
+            let after = self.completed().into_owned();
+            // Work the attempt took back is not submitted again.
+            if after.partial_cmp(&before) == Some(Ordering::Greater) {
+                self.unworked(after - before);
+            }
+        }
+        result
+    }
+
+    /// Run `attempt` until it succeeds, at most `attempts` times. The work submitted by failed attempts is taken back.
+    /// Returns the error of the last attempt if all attempts failed. `attempt` is always run at least once.
+    fn retry<T, E, F>(&mut self, attempts: usize, mut attempt: F) -> Result<T, E>
+    where
+        Self: Sized,
+        F: FnMut(&mut Self) -> Result<T, E>,
+    {
+        let mut tries = 1;
+        loop {
+            match self.attempt(&mut attempt) {
+                Err(_) if tries < attempts => {
+                    tracing::debug!("Attempt {tries} of {attempts} failed. Retrying.");
+                    tries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn total(&self) -> Cow<'_, W>;
 
-    fn total(&self) -> &W;
+    /// Replace the total amount of work, e.g. when more work was discovered while working.
+    /// Fails if the total does not cover the work already completed.
+    /// A child maps the parent work it has left onto the work it has left under the new total.
+    fn try_set_total<A: Into<W>>(&mut self, total: A) -> Result<(), WorkError>;
 
-    fn completed(&self) -> &W;
+    /// Replace the total amount of work. An invalid total is handled according to the `InvalidWorkPolicy` of this monitor.
+    /// See `try_set_total`.
+    fn set_total<A: Into<W>>(&mut self, total: A) {
+        if let Err(err) = self.try_set_total(total) {
+            self.config().on_invalid_work.handle(&err, self);
+        }
+    }
 
-    fn remaining(&self) -> Cow<W>;
+    /// Increase the total amount of work by `additional`. See `try_set_total`.
+    fn try_add_total<A: Into<W>>(&mut self, additional: A) -> Result<(), WorkError> {
+        let total = (self.total().into_owned() + additional.into())
+            .map_err(|err| WorkError::from_add(err, self.context()))?;
+        self.try_set_total(total)
+    }
+
+    /// Increase the total amount of work by `additional`.
+    /// An invalid total is handled according to the `InvalidWorkPolicy` of this monitor. See `try_set_total`.
+    fn add_total<A: Into<W>>(&mut self, additional: A) {
+        if let Err(err) = self.try_add_total(additional) {
+            self.config().on_invalid_work.handle(&err, self);
+        }
+    }
+
+    fn completed(&self) -> Cow<'_, W>;
+
+    fn remaining(&self) -> Cow<'_, W>;
 
     /// If you are done with your work, close this monitor.
-    fn close(&mut self) -> Result<(), CloseError>;
+    /// A cancelled monitor may be closed with work left, resulting in `CloseOutcome::Cancelled`.
+    fn close(&mut self) -> Result<CloseOutcome, CloseError>;
+
+    /// Submit all work remaining and close this monitor.
+    fn finish(&mut self) -> Result<CloseOutcome, CloseError> {
+        let remaining = self.remaining().into_owned();
+        if remaining != W::zero() {
+            self.worked(remaining);
+        }
+        self.close()
+    }
+
+    /// Close this monitor because its work became unnecessary, resulting in `CloseOutcome::Skipped`.
+    /// A child hands its parent all of the parent work it has left, without doing its own work.
+    /// A monitor which was already closed successfully stays closed, returning its original outcome.
+    fn skip<R: Into<String>>(&mut self, reason: R) -> CloseOutcome;
+
+    /// The token signalling cancellation of this monitor. Hand it to the code doing the work.
+    /// The tokens of children are derived from the token of their parent.
+    fn cancellation_token(&self) -> CancellationToken;
+
+    /// Cancel this monitor and all of its descendants.
+    fn cancel(&self) {
+        self.cancellation_token().cancel()
+    }
+
+    /// Whether this monitor or any of its ancestors was cancelled.
+    fn is_cancelled(&self) -> bool {
+        self.cancellation_token().is_cancelled()
+    }
+
+    /// Pause this monitor, e.g. while an operator halted the work. Time paused does not count as active time.
+    /// Subscribers are notified. Work may still be submitted while paused.
+    fn pause(&mut self);
+
+    /// Resume this monitor after it was paused.
+    fn resume(&mut self);
+
+    /// The time this monitor was active and paused.
+    fn clock(&self) -> Clock;
+
+    fn is_paused(&self) -> bool {
+        self.clock().is_paused()
+    }
+
+    /// Whether the total of this monitor is unknown. Its total is zero then and work is counted without a limit.
+    /// Setting a total makes the monitor determinate.
+    fn is_indeterminate(&self) -> bool {
+        false
+    }
+
+    /// Create a child whose total is unknown. See `CallbackProgressMonitor::indeterminate`.
+    /// Its `parent_work` is credited when the child is closed, or follows the child once its total is set.
+    /// Panics like `ProgressMonitorDivision::new_child`. See `try_new_indeterminate_child`.
+    fn new_indeterminate_child<'n, 'p, N: Into<Cow<'n, str>>, A: Into<W>>(
+        &'p mut self,
+        name: N,
+        parent_work: A,
+    ) -> ChildMonitor<'n, 'p, W, Self>
+    where
+        Self: Sized,
+    {
+        self.try_new_indeterminate_child(name, parent_work)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like `new_indeterminate_child`, but fails like `ProgressMonitorDivision::try_new_child`.
+    fn try_new_indeterminate_child<'n, 'p, N: Into<Cow<'n, str>>, A: Into<W>>(
+        &'p mut self,
+        name: N,
+        parent_work: A,
+    ) -> Result<ChildMonitor<'n, 'p, W, Self>, NewChildError>
+    where
+        Self: Sized,
+    {
+        let parent_work = self.config().on_excess_share.share(
+            || self.context(),
+            &[&parent_work.into()],
+            self.remaining().into_owned(),
+        )?;
+        Ok(ChildMonitor::new_indeterminate(
+            name.into(),
+            self,
+            parent_work,
+        ))
+    }
+
+    /// The configuration of this monitor. Children inherit the configuration of their parent.
+    fn config(&self) -> MonitorConfig {
+        MonitorConfig::default()
+    }
+
+    /// The id under which this monitor is known to the registry, if it registered itself.
+    #[cfg(feature = "registry")]
+    fn registry_id(&self) -> Option<crate::registry::MonitorId> {
+        None
+    }
 }
 
 pub trait ProgressMonitorDivision<'p, 'n, N, W, A1, A2>
@@ -29,6 +268,8 @@ where
     A1: Into<W>,
     A2: Into<W>,
 {
+    /// Panics if `parent_work` exceeds the remaining work of this monitor, unless `ExcessSharePolicy::Clamp` is configured.
+    /// See `try_new_child`.
     fn new_child(
         // A reference to the parent monitor.
         &'p mut self,
@@ -38,5 +279,91 @@ where
         parent_work: A1,
         // The child monitors scale for the work taken from the parent monitor. Can be arbitrary.
         child_work: A2,
-    ) -> ChildMonitor<'n, 'p, W, Self>;
+    ) -> ChildMonitor<'n, 'p, W, Self> {
+        self.try_new_child(name, parent_work, child_work)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like `new_child`, but fails if `parent_work` exceeds the remaining work of this monitor.
+    /// With `ExcessSharePolicy::Clamp` configured, the child is handed whatever work remains instead.
+    fn try_new_child(
+        &'p mut self,
+        name: N,
+        parent_work: A1,
+        child_work: A2,
+    ) -> Result<ChildMonitor<'n, 'p, W, Self>, NewChildError> {
+        let parent_work = self.config().on_excess_share.share(
+            || self.context(),
+            &[&parent_work.into()],
+            self.remaining().into_owned(),
+        )?;
+        Ok(ChildMonitor::new(
+            name.into(),
+            self,
+            parent_work,
+            child_work.into(),
+        ))
+    }
+
+    /// Create a `DetachedChildMonitor`, which does not borrow this monitor. See `new_child`.
+    /// Its progress reaches this monitor through the returned receiver.
+    ///
+    /// Panics if `parent_work` exceeds the remaining work of this monitor, unless `ExcessSharePolicy::Clamp` is configured.
+    /// See `try_new_detached_child`.
+    fn new_detached_child(
+        &mut self,
+        name: N,
+        parent_work: A1,
+        child_work: A2,
+    ) -> (DetachedChildMonitor<W>, DetachedChildReceiver<W>) {
+        self.try_new_detached_child(name, parent_work, child_work)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like `new_detached_child`, but fails if `parent_work` exceeds the remaining work of this monitor.
+    /// As the child does not borrow this monitor, its parent work is not reserved: Work submitted to this monitor directly
+    /// may still use it up.
+    fn try_new_detached_child(
+        &mut self,
+        name: N,
+        parent_work: A1,
+        child_work: A2,
+    ) -> Result<(DetachedChildMonitor<W>, DetachedChildReceiver<W>), NewChildError> {
+        let parent_work = self.config().on_excess_share.share(
+            || self.context(),
+            &[&parent_work.into()],
+            self.remaining().into_owned(),
+        )?;
+        Ok(DetachedChildMonitor::child_of(
+            Cow::Owned(name.into().into_owned()),
+            self,
+            parent_work,
+            child_work.into(),
+        ))
+    }
+
+    /// Split off multiple children at once, each one described by a `(name, parent_work, child_work)` triple.
+    /// In contrast to `new_child`, the children can be alive at the same time and be handed to different threads.
+    /// The accumulated `parent_work` of all parts must be <= the remaining work of this monitor!
+    fn split<I>(&'p mut self, parts: I) -> Vec<SharedProgressMonitor<'p, W>>
+    where
+        I: IntoIterator<Item = (N, A1, A2)>,
+        Self: Send,
+        W: Send,
+        'n: 'p,
+    {
+        self.try_split(parts).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like `split`, but fails if the accumulated `parent_work` exceeds the remaining work of this monitor.
+    /// Parts are never clamped, independent of the configured `ExcessSharePolicy`.
+    fn try_split<I>(&'p mut self, parts: I) -> Result<Vec<SharedProgressMonitor<'p, W>>, NewChildError>
+    where
+        I: IntoIterator<Item = (N, A1, A2)>,
+        Self: Send,
+        W: Send,
+        'n: 'p,
+    {
+        SharedProgressMonitor::split_monitor(self, parts)
+    }
 }
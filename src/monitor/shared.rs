@@ -0,0 +1,775 @@
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    fmt::{Debug, Display},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use crate::{
+    error::{NewChildError, WorkError},
+    work::Work,
+    CloseError,
+};
+
+use super::{
+    cancel::CancellationToken,
+    clock::Clock,
+    config::{ExcessSharePolicy, MonitorConfig},
+    state::{MonitorState, ParentShare},
+    subscription::Subscription,
+    CloseOutcome, ProgressMonitor,
+};
+
+pub(crate) type SharedCallback<'n, W> = Box<dyn Fn(&W, &W) + Send + 'n>;
+type Forward<'n, W> = Box<dyn FnMut(Submitted<W>) -> Result<(), WorkError> + Send + 'n>;
+
+/// Work forwarded to the monitor which was split.
+enum Submitted<W> {
+    Worked(W),
+    Unworked(W),
+}
+
+/// A progress monitor which can be cloned and handed to other threads.
+///
+/// All clones refer to the same monitor. Work submitted through any clone advances that monitor.
+/// Work submitted directly may not use up the parent work of children which are still alive. It overshoots instead.
+/// Children created through `new_child` are shareable themselves and report their progress into their parent.
+/// In contrast to `ChildMonitor`, any number of children can be alive at the same time.
+///
+/// The monitor is `Send + Sync` whenever `W` is `Send`.
+/// Callbacks are invoked while the monitor and the child submitting work are locked.
+/// They must not submit work to, or read from, the monitor they are called from or any of its children.
+pub struct SharedProgressMonitor<'n, W: Work> {
+    node: Arc<Mutex<SharedNode<'n, W>>>,
+}
+
+enum Upstream<'n, W: Work> {
+    /// A root monitor reports its progress to a callback.
+    Callback(SharedCallback<'n, W>),
+    /// A monitor splitting some other (borrowed) monitor forwards all its progress to it.
+    Forward(Forward<'n, W>),
+    /// A child monitor reports its progress to its parent.
+    Parent {
+        parent: SharedProgressMonitor<'n, W>,
+        share: ParentShare<W>,
+    },
+}
+
+struct SharedNode<'n, W: Work> {
+    state: MonitorState<'n, W>,
+    /// Parent work handed out to children which they have not yet submitted back.
+    reserved: W,
+    upstream: Upstream<'n, W>,
+}
+
+impl<'n, W: Work> SharedProgressMonitor<'n, W> {
+    pub fn new<N, A, C>(name: N, work: A, callback: C) -> Self
+    where
+        N: Into<Cow<'n, str>>,
+        A: Into<W>,
+        C: Fn(&W, &W) + Send + 'n,
+    {
+        Self::from_node(SharedNode {
+            state: MonitorState::new(name.into(), work.into()),
+            reserved: W::zero(),
+            upstream: Upstream::Callback(Box::new(callback)),
+        })
+    }
+
+    /// A monitor whose total is unknown. See `CallbackProgressMonitor::indeterminate`.
+    pub fn indeterminate<N, C>(name: N, callback: C) -> Self
+    where
+        N: Into<Cow<'n, str>>,
+        C: Fn(&W, &W) + Send + 'n,
+    {
+        Self::from_node(SharedNode {
+            state: MonitorState::new(name.into(), W::zero()).indeterminate(),
+            reserved: W::zero(),
+            upstream: Upstream::Callback(Box::new(callback)),
+        })
+    }
+
+    fn from_node(node: SharedNode<'n, W>) -> Self {
+        Self {
+            node: Arc::new(Mutex::new(node)),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SharedNode<'n, W>> {
+        // A panic in some other thread does not render our bookkeeping invalid.
+        self.node.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn name(&self) -> Cow<'n, str> {
+        self.lock().state.name.clone()
+    }
+
+    /// Changes the configuration of this monitor, affecting all its clones. Children created later on inherit it.
+    pub fn with_config(self, config: MonitorConfig) -> Self {
+        self.lock().state.config = config;
+        self
+    }
+
+    /// Observe the progress of this monitor from somewhere else, e.g. another thread.
+    pub fn subscribe(&self) -> Subscription<W> {
+        self.lock().state.subscribe()
+    }
+
+    /// Sets the total amount of work to `work`, if this monitor was created with a total of zero and no work was done yet.
+    /// An indeterminate monitor becomes determinate. Returns whether the total was set.
+    pub fn infer_total<A: Into<W>>(&self, work: A) -> bool {
+        let mut node = self.lock();
+        if node.state.work != W::zero() || node.state.work_done != W::zero() {
+            return false;
+        }
+        node.state.try_set_total(work.into()).is_ok()
+    }
+
+    /// Create a child monitor, responsible for `parent_work` of this monitors work, using its own scale of `child_work`.
+    /// The child can be cloned and handed to other threads. Any number of children may be alive at the same time.
+    ///
+    /// Panics if `parent_work` exceeds the work remaining in this monitor which is not yet handed out to other children,
+    /// unless `ExcessSharePolicy::Clamp` is configured. See `try_new_child`.
+    pub fn new_child<N, A1, A2>(&self, name: N, parent_work: A1, child_work: A2) -> Self
+    where
+        N: Into<Cow<'n, str>>,
+        A1: Into<W>,
+        A2: Into<W>,
+    {
+        self.try_new_child(name, parent_work, child_work)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like `new_child`, but fails if `parent_work` exceeds the work remaining in this monitor
+    /// which is not yet handed out to other children.
+    /// With `ExcessSharePolicy::Clamp` configured, the child is handed whatever work remains instead.
+    pub fn try_new_child<N, A1, A2>(
+        &self,
+        name: N,
+        parent_work: A1,
+        child_work: A2,
+    ) -> Result<Self, NewChildError>
+    where
+        N: Into<Cow<'n, str>>,
+        A1: Into<W>,
+        A2: Into<W>,
+    {
+        let parent_work = self.reserve(&[&parent_work.into()], self.config().on_excess_share)?;
+        let state = MonitorState::new(name.into(), child_work.into()).child_of(self);
+        Ok(self.child(state, parent_work))
+    }
+
+    /// Create a child whose total is unknown. See `ProgressMonitor::new_indeterminate_child`.
+    /// Panics like `new_child`.
+    pub fn new_indeterminate_child<N, A>(&self, name: N, parent_work: A) -> Self
+    where
+        N: Into<Cow<'n, str>>,
+        A: Into<W>,
+    {
+        self.try_new_indeterminate_child(name, parent_work)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like `new_indeterminate_child`, but fails like `try_new_child`.
+    pub fn try_new_indeterminate_child<N, A>(
+        &self,
+        name: N,
+        parent_work: A,
+    ) -> Result<Self, NewChildError>
+    where
+        N: Into<Cow<'n, str>>,
+        A: Into<W>,
+    {
+        let parent_work = self.reserve(&[&parent_work.into()], self.config().on_excess_share)?;
+        let state = MonitorState::new(name.into(), W::zero())
+            .child_of(self)
+            .indeterminate();
+        Ok(self.child(state, parent_work))
+    }
+
+    fn child(&self, state: MonitorState<'n, W>, parent_work: W) -> Self {
+        Self::from_node(SharedNode {
+            state,
+            reserved: W::zero(),
+            upstream: Upstream::Parent {
+                parent: self.clone(),
+                share: ParentShare::new(parent_work),
+            },
+        })
+    }
+
+    /// Create multiple children at once, each one described by a `(name, parent_work, child_work)` triple.
+    /// See `new_child`. The children can be handed to different threads and report into this monitor concurrently.
+    ///
+    /// Panics if the accumulated `parent_work` of all parts exceeds the work remaining in this monitor
+    /// which is not yet handed out to other children. See `try_split`.
+    pub fn split<N, A1, A2, I>(&self, parts: I) -> Vec<Self>
+    where
+        N: Into<Cow<'n, str>>,
+        A1: Into<W>,
+        A2: Into<W>,
+        I: IntoIterator<Item = (N, A1, A2)>,
+    {
+        self.try_split(parts).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like `split`, but fails if the accumulated `parent_work` of all parts exceeds the work remaining in this monitor
+    /// which is not yet handed out to other children. Parts are never clamped.
+    pub fn try_split<N, A1, A2, I>(&self, parts: I) -> Result<Vec<Self>, NewChildError>
+    where
+        N: Into<Cow<'n, str>>,
+        A1: Into<W>,
+        A2: Into<W>,
+        I: IntoIterator<Item = (N, A1, A2)>,
+    {
+        let parts: Vec<(Cow<'n, str>, W, W)> = parts
+            .into_iter()
+            .map(|(name, parent_work, child_work)| {
+                (name.into(), parent_work.into(), child_work.into())
+            })
+            .collect();
+        let shares: Vec<&W> = parts
+            .iter()
+            .map(|(_, parent_work, _)| parent_work)
+            .collect();
+        self.reserve(&shares, ExcessSharePolicy::Reject)?;
+        Ok(parts
+            .into_iter()
+            .map(|(name, parent_work, child_work)| {
+                self.child(
+                    MonitorState::new(name, child_work).child_of(self),
+                    parent_work,
+                )
+            })
+            .collect())
+    }
+
+    /// Splits the borrowed `parent` into shareable children. See `ProgressMonitorDivision::split`.
+    pub(crate) fn split_monitor<'a, P, N, A1, A2, I>(
+        parent: &'n mut P,
+        parts: I,
+    ) -> Result<Vec<Self>, NewChildError>
+    where
+        'a: 'n,
+        P: ProgressMonitor<W> + Send,
+        W: Send,
+        N: Into<Cow<'a, str>>,
+        A1: Into<W>,
+        A2: Into<W>,
+        I: IntoIterator<Item = (N, A1, A2)>,
+    {
+        let parts: Vec<(Cow<'n, str>, W, W)> = parts
+            .into_iter()
+            .map(|(name, parent_work, child_work)| {
+                let name: Cow<'a, str> = name.into();
+                (name, parent_work.into(), child_work.into())
+            })
+            .collect();
+        let shares: Vec<&W> = parts
+            .iter()
+            .map(|(_, parent_work, _)| parent_work)
+            .collect();
+        let parent_work = ExcessSharePolicy::Reject.share(
+            || parent.context(),
+            &shares,
+            parent.remaining().into_owned(),
+        )?;
+
+        // An intermediate monitor, owning the parent work of all parts and handing it out to the individual parts.
+        let hub = Self::from_node(SharedNode {
+            state: MonitorState::new(Cow::Owned(parent.name().into_owned()), parent_work)
+                .stand_in_for(&*parent),
+            reserved: W::zero(),
+            upstream: Upstream::Forward(Box::new(move |submitted| match submitted {
+                Submitted::Worked(amount) => parent.try_worked(amount),
+                Submitted::Unworked(amount) => parent.try_unworked(amount),
+            })),
+        });
+        hub.try_split(parts)
+    }
+
+    /// Reserves the work requested by new children. Returns the work actually reserved. See `ExcessSharePolicy`.
+    fn reserve(&self, shares: &[&W], policy: ExcessSharePolicy) -> Result<W, NewChildError> {
+        let mut node = self.lock();
+        let available = node.state.remaining() - node.reserved.clone();
+        let reserved = policy.share(|| node.state.context(), shares, available)?;
+        node.reserved = (node.reserved.clone() + reserved.clone())
+            .expect("Reserved work never exceeds the total work");
+        Ok(reserved)
+    }
+
+    /// Advances this monitor by work which was completed in one of its children.
+    fn child_worked(&self, amount: W) -> Result<(), WorkError> {
+        self.work_releasing(amount.clone(), amount)
+    }
+
+    /// Stops reserving `amount` of work for children, e.g. because they are gone.
+    fn release(&self, amount: W) {
+        let mut node = self.lock();
+        let released = W::min(&amount, &node.reserved).clone();
+        node.reserved = node.reserved.clone() - released;
+    }
+
+    /// Takes back work which was taken back in one of its children.
+    fn child_unworked(&self, amount: W) -> Result<(), WorkError> {
+        // The child must submit the work again.
+        self.unwork_reserving(amount.clone(), amount)
+    }
+
+    /// Submit work through a shared reference. Invalid work is handled according to the configured `InvalidWorkPolicy`.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn work(&self, amount: W) {
+        if let Err(err) = self.try_work(amount) {
+            self.config().on_invalid_work.handle(&err, self);
+        }
+    }
+
+    fn try_work(&self, amount: W) -> Result<(), WorkError> {
+        self.work_releasing(amount, W::zero())
+    }
+
+    fn try_unwork(&self, amount: W) -> Result<(), WorkError> {
+        self.unwork_reserving(amount, W::zero())
+    }
+
+    /// Advances this monitor by `amount`, of which `released` was reserved for children and is no longer.
+    /// Work rejected upstream is rejected by this monitor as well, leaving it as it was.
+    fn work_releasing(&self, amount: W, released: W) -> Result<(), WorkError> {
+        // Our lock is held while forwarding the work. Monitors are only ever locked from children towards their root,
+        // so this cannot deadlock.
+        let mut guard = self.lock();
+        let node = &mut *guard;
+        let checkpoint = node.state.checkpoint();
+        let reserved = node.reserved.clone();
+        let released = W::min(&released, &node.reserved).clone();
+        node.reserved = node.reserved.clone() - released;
+        // Work handed out to children which are still alive is not available to anyone else.
+        let accepted = match node.state.try_worked_reserving(amount, &node.reserved) {
+            Ok(accepted) => accepted,
+            Err(err) => {
+                node.reserved = reserved;
+                return Err(err);
+            }
+        };
+        let state = &node.state;
+        let forwarded = match &mut node.upstream {
+            Upstream::Callback(callback) => {
+                callback(&state.work, &state.work_done);
+                Ok(())
+            }
+            Upstream::Forward(forward) => forward(Submitted::Worked(accepted)),
+            Upstream::Parent { parent, share } => {
+                share.atomically(|share| parent.child_worked(share.advance(state)?))
+            }
+        };
+        if forwarded.is_err() {
+            node.state.restore(checkpoint);
+            node.reserved = reserved;
+        }
+        forwarded
+    }
+
+    /// Takes back `amount` of work, reserving `reserved` of it for children again.
+    /// Work rejected upstream is rejected by this monitor as well, leaving it as it was.
+    fn unwork_reserving(&self, amount: W, reserved: W) -> Result<(), WorkError> {
+        let mut guard = self.lock();
+        let node = &mut *guard;
+        let checkpoint = node.state.checkpoint();
+        let taken = node.state.try_unworked(amount)?;
+        let previously_reserved = node.reserved.clone();
+        node.reserved =
+            (node.reserved.clone() + reserved).expect("Reserved work never exceeds the total work");
+        let state = &node.state;
+        let forwarded = match &mut node.upstream {
+            Upstream::Callback(callback) => {
+                callback(&state.work, &state.work_done);
+                Ok(())
+            }
+            Upstream::Forward(forward) => forward(Submitted::Unworked(taken)),
+            Upstream::Parent { parent, share } => {
+                share.atomically(|share| parent.child_unworked(share.retreat(state)?))
+            }
+        };
+        if forwarded.is_err() {
+            node.state.restore(checkpoint);
+            node.reserved = previously_reserved;
+        }
+        forwarded
+    }
+}
+
+impl<'n, W: Work> Clone for SharedProgressMonitor<'n, W> {
+    fn clone(&self) -> Self {
+        Self {
+            node: Arc::clone(&self.node),
+        }
+    }
+}
+
+impl<'n, W: Work> ProgressMonitor<W> for SharedProgressMonitor<'n, W> {
+    fn name(&self) -> Cow<'_, str> {
+        Cow::Owned(self.lock().state.name.to_string())
+    }
+
+    fn try_worked<A: Into<W>>(&mut self, amount_of_work: A) -> Result<(), WorkError> {
+        self.try_work(amount_of_work.into())
+    }
+
+    fn try_unworked<A: Into<W>>(&mut self, amount_of_work: A) -> Result<(), WorkError> {
+        self.try_unwork(amount_of_work.into())
+    }
+
+    fn total(&self) -> Cow<'_, W> {
+        Cow::Owned(self.lock().state.work.clone())
+    }
+
+    fn try_set_total<A: Into<W>>(&mut self, total: A) -> Result<(), WorkError> {
+        let total = total.into();
+        let mut guard = self.lock();
+        let node = &mut *guard;
+        // Work handed out to children must stay covered as well.
+        let required = (node.state.work_done.clone() + node.reserved.clone())
+            .map_err(|err| WorkError::from_add(err, node.state.context()))?;
+        if !matches!(
+            required.partial_cmp(&total),
+            Some(Ordering::Less | Ordering::Equal)
+        ) {
+            return Err(WorkError::TotalBelowCompleted {
+                monitor: Box::new(node.state.context()),
+                total: total.to_string(),
+            });
+        }
+        let checkpoint = node.state.checkpoint();
+        node.state.try_set_total(total)?;
+        let state = &node.state;
+        let forwarded = match &mut node.upstream {
+            Upstream::Callback(callback) => {
+                callback(&state.work, &state.work_done);
+                Ok(())
+            }
+            // Parts of a split monitor are its only children. Its total never changes.
+            Upstream::Forward(_) => Ok(()),
+            Upstream::Parent { parent, share } => {
+                share.atomically(|share| match share.rescale(state)? {
+                    Some(parent_worked) => parent.child_worked(parent_worked),
+                    None => Ok(()),
+                })
+            }
+        };
+        if forwarded.is_err() {
+            node.state.restore(checkpoint);
+        }
+        forwarded
+    }
+
+    fn completed(&self) -> Cow<'_, W> {
+        Cow::Owned(self.lock().state.work_done.clone())
+    }
+
+    fn remaining(&self) -> Cow<'_, W> {
+        Cow::Owned(self.lock().state.remaining())
+    }
+
+    fn close(&mut self) -> Result<CloseOutcome, CloseError> {
+        let (result, forward) = {
+            let mut guard = self.lock();
+            let node = &mut *guard;
+            let indeterminate = node.state.indeterminate;
+            let result = node.state.close();
+            let forward = match &mut node.upstream {
+                // Not knowing how far along the child was, its parent work is credited as a whole.
+                Upstream::Parent { parent, share }
+                    if indeterminate && result == Ok(CloseOutcome::Completed) =>
+                {
+                    Some((parent.clone(), share.rest()))
+                }
+                _ => None,
+            };
+            (result, forward)
+        };
+        if let Some((parent, parent_worked)) = forward {
+            if parent_worked != W::zero() {
+                if let Err(err) = parent.child_worked(parent_worked) {
+                    parent.config().on_invalid_work.handle(&err, &parent);
+                }
+            }
+        }
+        result
+    }
+
+    fn pause(&mut self) {
+        self.lock().state.pause()
+    }
+
+    fn resume(&mut self) {
+        self.lock().state.resume()
+    }
+
+    fn clock(&self) -> Clock {
+        self.lock().state.clock
+    }
+
+    fn skip<R: Into<String>>(&mut self, reason: R) -> CloseOutcome {
+        let (outcome, forward) = {
+            let mut guard = self.lock();
+            let node = &mut *guard;
+            let forward = match &mut node.upstream {
+                Upstream::Parent { parent, share } if !node.state.is_closed() => {
+                    Some((parent.clone(), share.rest()))
+                }
+                _ => None,
+            };
+            (node.state.skip(reason.into()), forward)
+        };
+        if let Some((parent, parent_worked)) = forward {
+            if parent_worked != W::zero() {
+                if let Err(err) = parent.child_worked(parent_worked) {
+                    parent.config().on_invalid_work.handle(&err, &parent);
+                }
+            }
+        }
+        outcome
+    }
+
+    fn is_indeterminate(&self) -> bool {
+        self.lock().state.indeterminate
+    }
+
+    fn cancellation_token(&self) -> CancellationToken {
+        self.lock().state.token.clone()
+    }
+
+    fn config(&self) -> MonitorConfig {
+        self.lock().state.config
+    }
+
+    fn path(&self) -> Vec<String> {
+        self.lock().state.path.clone()
+    }
+
+    #[cfg(feature = "registry")]
+    fn registry_id(&self) -> Option<crate::registry::MonitorId> {
+        self.lock().state.registration.id()
+    }
+}
+
+impl<'n, W: Work> Debug for SharedProgressMonitor<'n, W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let node = self.lock();
+        f.debug_struct("SharedProgressMonitor")
+            .field("name", &node.state.name)
+            .field("work", &node.state.work)
+            .field("work_done", &node.state.work_done)
+            .finish()
+    }
+}
+
+impl<'n, W: Work> Display for SharedProgressMonitor<'n, W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let node = self.lock();
+        f.write_fmt(format_args!(
+            "{}/{}",
+            node.state.work_done,
+            node.state.total_display()
+        ))
+    }
+}
+
+/// Runs when the last clone of a monitor is dropped.
+impl<'n, W: Work> Drop for SharedNode<'n, W> {
+    fn drop(&mut self) {
+        if let Upstream::Forward(_) = self.upstream {
+            // Intermediate monitors created by splitting are dropped when their last part is gone.
+            // Parts which were not completed already reported that themselves.
+            let _ = self.state.close();
+            return;
+        }
+        match &self.state.closed {
+            Some(Ok(_)) => { /* do nothing */ }
+            Some(Err(err)) => {
+                tracing::error!(
+                    "SharedProgressMonitor was not successfully closed. Reason: {}",
+                    err
+                );
+            }
+            None if self.state.token.is_cancelled() => {
+                let _ = self.state.close();
+            }
+            None => {
+                tracing::warn!("close() was not called on {:?}!", self.state);
+                if let Err(err) = self.state.close() {
+                    tracing::error!("SharedProgressMonitor could not be closed. Reason: {}", err);
+                }
+            }
+        }
+        if let Upstream::Parent { parent, share } = &self.upstream {
+            // The parent work this child did not submit may be done by someone else now.
+            parent.release(share.parent_work.clone() - share.parent_work_submitted.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn children_report_from_multiple_threads() {
+        let mut root = SharedProgressMonitor::new(
+            "root",
+            100u64,
+            |_: &NumericWork<u64>, _: &NumericWork<u64>| {},
+        );
+
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let mut child = root.new_child(format!("worker-{i}"), 25u64, 1000u64);
+                thread::spawn(move || {
+                    for _ in 0..10 {
+                        child.worked(100u64);
+                    }
+                    child.close().unwrap();
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(root.completed().as_ref(), &NumericWork::new(100u64));
+        root.close().unwrap();
+    }
+
+    #[test]
+    fn split_borrowed_monitor() {
+        let mut root = CallbackProgressMonitor::silent("root", 100);
+        let parts = root.split([("download", 50u64, 1000u64), ("index", 30u64, 10u64)]);
+        assert_eq!(parts[0].path(), vec!["root", "download"]);
+        thread::scope(|s| {
+            for mut part in parts {
+                s.spawn(move || {
+                    let total = part.total().into_owned();
+                    part.worked(total);
+                    part.close().unwrap();
+                });
+            }
+        });
+        assert_eq!(root.completed().as_ref(), &NumericWork::new(80u64));
+        root.worked(20u64);
+        root.close().unwrap();
+    }
+
+    #[test]
+    fn direct_work_leaves_reserved_work_to_children() {
+        let mut root = SharedProgressMonitor::new(
+            "root",
+            10u64,
+            |_: &NumericWork<u64>, _: &NumericWork<u64>| {},
+        );
+        let mut child = root.new_child("child", 5u64, 5u64);
+        root.clone().worked(10u64);
+        assert_eq!(root.completed().as_ref(), &NumericWork::new(5u64));
+        let mut strict = root.clone().with_config(MonitorConfig {
+            on_overshoot: OvershootPolicy::Error,
+            ..MonitorConfig::default()
+        });
+        assert!(matches!(
+            strict.try_worked(1u64),
+            Err(WorkError::Overshoot { .. })
+        ));
+        child.worked(5u64);
+        child.close().unwrap();
+        assert_eq!(root.completed().as_ref(), &NumericWork::new(10u64));
+        root.close().unwrap();
+
+        // Children gone without doing their work no longer hold on to it.
+        let mut root = SharedProgressMonitor::new(
+            "root",
+            10u64,
+            |_: &NumericWork<u64>, _: &NumericWork<u64>| {},
+        );
+        let mut child = root.new_child("cancelled", 5u64, 5u64);
+        child.cancel();
+        child.close().unwrap();
+        drop(child);
+        root.worked(10u64);
+        assert_eq!(root.completed().as_ref(), &NumericWork::new(10u64));
+        root.close().unwrap();
+    }
+
+    #[test]
+    fn concurrent_direct_work_never_takes_work_submitted_by_children() {
+        let mut root = SharedProgressMonitor::new(
+            "root",
+            1000u64,
+            |_: &NumericWork<u64>, _: &NumericWork<u64>| {},
+        )
+        .with_config(MonitorConfig {
+            on_overshoot: OvershootPolicy::Error,
+            ..MonitorConfig::default()
+        });
+        let mut child = root.new_child("child", 500u64, 500u64);
+        let mut direct = root.clone();
+        thread::scope(|s| {
+            s.spawn(|| {
+                for _ in 0..1000 {
+                    let _ = direct.try_worked(1u64);
+                }
+            });
+            s.spawn(|| {
+                for _ in 0..500 {
+                    child.try_worked(1u64).unwrap();
+                }
+            });
+        });
+        child.close().unwrap();
+        assert_eq!(root.completed().as_ref(), &NumericWork::new(1000u64));
+        root.close().unwrap();
+    }
+
+    #[test]
+    fn children_rejected_by_their_parent_are_left_unchanged() {
+        let mut root = SharedProgressMonitor::new(
+            "root",
+            100u64,
+            |_: &NumericWork<u64>, _: &NumericWork<u64>| {},
+        );
+        let mut child = root.new_child("child", 50u64, 10u64);
+        root.cancel();
+        assert_eq!(root.close(), Ok(CloseOutcome::Cancelled));
+        assert!(matches!(
+            child.try_worked(5u64),
+            Err(WorkError::Closed { .. })
+        ));
+        assert_eq!(child.completed().as_ref(), &NumericWork::zero());
+        assert_eq!(child.close(), Ok(CloseOutcome::Cancelled));
+    }
+
+    #[test]
+    fn indeterminate_monitors_and_children() {
+        let mut stream = SharedProgressMonitor::indeterminate(
+            "stream",
+            |_: &NumericWork<u64>, _: &NumericWork<u64>| {},
+        );
+        stream.worked(1000u64);
+        assert!(stream.is_indeterminate());
+        assert_eq!(stream.to_string(), "1000/?");
+        assert_eq!(stream.close(), Ok(CloseOutcome::Completed));
+
+        let mut root = SharedProgressMonitor::new(
+            "root",
+            100u64,
+            |_: &NumericWork<u64>, _: &NumericWork<u64>| {},
+        );
+        let mut crawl = root.new_indeterminate_child("crawl", 40u64);
+        assert!(root.try_new_indeterminate_child("more", 70u64).is_err());
+        crawl.worked(10u64);
+        assert_eq!(root.completed().as_ref(), &NumericWork::zero());
+        assert_eq!(crawl.close(), Ok(CloseOutcome::Completed));
+        assert_eq!(root.completed().as_ref(), &NumericWork::new(40u64));
+        root.worked(60u64);
+        root.close().unwrap();
+    }
+}
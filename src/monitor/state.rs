@@ -0,0 +1,493 @@
+use std::{borrow::Cow, cmp::Ordering, fmt::Debug};
+
+use crate::{
+    error::{CloseError, MonitorContext, WorkError},
+    work::Work,
+};
+
+use super::{
+    cancel::CancellationToken,
+    clock::Clock,
+    config::{MonitorConfig, OvershootPolicy},
+    subscription::{ProgressSnapshot, Subscribers, Subscription},
+    CloseOutcome, ProgressMonitor,
+};
+
+/// The bookkeeping every monitor has to do, independent of where its progress is reported to.
+/// Tracks the total amount of work, how much of it was completed and whether the monitor was closed.
+#[derive(Debug)]
+pub(crate) struct MonitorState<'n, W: Work> {
+    pub(crate) name: Cow<'n, str>,
+    pub(crate) work: W,
+    pub(crate) work_done: W,
+    pub(crate) closed: Option<Result<CloseOutcome, CloseError>>,
+    pub(crate) config: MonitorConfig,
+    /// The names of all monitors from the root down to this monitor.
+    pub(crate) path: Vec<String>,
+    pub(crate) token: CancellationToken,
+    pub(crate) clock: Clock,
+    /// Whether the total is unknown. The total stays zero until it is set.
+    pub(crate) indeterminate: bool,
+    subscribers: Subscribers<W>,
+    #[cfg(feature = "registry")]
+    pub(crate) registration: crate::registry::Registration,
+}
+
+impl<'n, W: Work> MonitorState<'n, W> {
+    pub(crate) fn new(name: Cow<'n, str>, work: W) -> Self {
+        Self {
+            #[cfg(feature = "registry")]
+            registration: crate::registry::register(&name, &work, &W::zero()),
+            path: vec![name.to_string()],
+            name,
+            work,
+            work_done: W::zero(),
+            closed: None,
+            config: MonitorConfig::default(),
+            token: CancellationToken::new(),
+            clock: Clock::start(),
+            indeterminate: false,
+            subscribers: Subscribers::new(),
+        }
+    }
+
+    /// Inherits the configuration and cancellation of `parent` and records it as the parent of this monitor in the registry.
+    pub(crate) fn child_of<P: ProgressMonitor<W>>(mut self, parent: &P) -> Self {
+        self.config = parent.config();
+        self.path = parent.path();
+        self.path.push(self.name.to_string());
+        self.token = parent.cancellation_token().child_token();
+        #[cfg(feature = "registry")]
+        self.registration.set_parent(parent.registry_id());
+        self
+    }
+
+    /// Stands in for `parent`, e.g. as the intermediate monitor of a split.
+    /// Like `child_of`, but takes over the path of `parent` and has no registry entry of its own, so that children appear directly below `parent`.
+    pub(crate) fn stand_in_for<P: ProgressMonitor<W>>(mut self, parent: &P) -> Self {
+        self.config = parent.config();
+        self.path = parent.path();
+        self.token = parent.cancellation_token().child_token();
+        #[cfg(feature = "registry")]
+        {
+            self.registration = crate::registry::Registration::alias(parent.registry_id());
+        }
+        self
+    }
+
+    /// Forgets the total, counting work done until a total is set.
+    pub(crate) fn indeterminate(mut self) -> Self {
+        self.work = W::zero();
+        self.indeterminate = true;
+        self.publish();
+        self
+    }
+
+    /// The total for display purposes, `?` if unknown.
+    pub(crate) fn total_display(&self) -> String {
+        match self.indeterminate {
+            true => "?".to_owned(),
+            false => self.work.to_string(),
+        }
+    }
+
+    pub(crate) fn context(&self) -> MonitorContext {
+        MonitorContext {
+            name: self.name.to_string(),
+            path: self.path.clone(),
+            total: self.total_display(),
+            completed: self.work_done.to_string(),
+            remaining: self.remaining().to_string(),
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> ProgressSnapshot<W> {
+        ProgressSnapshot {
+            name: self.name.to_string(),
+            total: self.work.clone(),
+            completed: self.work_done.clone(),
+            closed: self.closed.clone(),
+            paused: self.clock.is_paused(),
+            indeterminate: self.indeterminate,
+            active_time: self.clock.active_time(),
+        }
+    }
+
+    pub(crate) fn subscribe(&mut self) -> Subscription<W> {
+        self.subscribers.subscribe(self.snapshot())
+    }
+
+    fn publish(&mut self) {
+        #[cfg(feature = "registry")]
+        self.registration.update(
+            &self.total_display(),
+            &self.work_done,
+            self.closed.is_some(),
+            self.clock.is_paused(),
+        );
+        if !self.subscribers.is_empty() {
+            let snapshot = self.snapshot();
+            self.subscribers.publish(snapshot);
+        }
+    }
+
+    /// Advances the work done, handling overshooting as configured.
+    /// Returns the amount of work which was actually accepted. Invalid work is rejected as a whole.
+    pub(crate) fn try_worked(&mut self, amount: W) -> Result<W, WorkError> {
+        self.try_worked_reserving(amount, &W::zero())
+    }
+
+    /// Like `try_worked`, but keeps `reserved` work of the total available, e.g. for children which are still alive.
+    /// Work reaching into the reserved work overshoots.
+    pub(crate) fn try_worked_reserving(&mut self, amount: W, reserved: &W) -> Result<W, WorkError> {
+        if let Some(Ok(_)) = self.closed {
+            return Err(WorkError::Closed {
+                monitor: Box::new(self.context()),
+            });
+        }
+        if self.indeterminate {
+            self.work_done = (self.work_done.clone() + amount.clone())
+                .map_err(|err| WorkError::from_add(err, self.context()))?;
+            self.publish();
+            return Ok(amount);
+        }
+        if let Some(item) = amount.unknown_item(&self.work) {
+            return Err(WorkError::UnknownItem {
+                monitor: Box::new(self.context()),
+                item,
+            });
+        }
+        let accepted = self.advance(amount, reserved)?;
+        self.publish();
+        Ok(accepted)
+    }
+
+    fn advance(&mut self, amount: W, reserved: &W) -> Result<W, WorkError> {
+        let now: W = (self.work_done.clone() + amount.clone())
+            .map_err(|err| WorkError::from_add(err, self.context()))?;
+        let limit = self.work.clone() - reserved.clone();
+        if now > limit {
+            match self.config.on_overshoot {
+                OvershootPolicy::ClampSilently => {}
+                OvershootPolicy::ClampAndWarn => tracing::warn!(
+                    work = ?self.work,
+                    work_done = ?self.work_done,
+                    new_work_done = ?amount,
+                    would_become = ?now,
+                    "Detected overshoot. Try to only submit work left open. Ignoring additional work."
+                ),
+                OvershootPolicy::Error => {
+                    return Err(WorkError::Overshoot {
+                        monitor: Box::new(self.context()),
+                        submitted: amount.to_string(),
+                    })
+                }
+                OvershootPolicy::Panic => panic!(
+                    "Detected overshoot in {self:?}. Submitted {amount} when only {} was remaining.",
+                    self.remaining()
+                ),
+                OvershootPolicy::GrowTotal => {
+                    self.work = (now.clone() + reserved.clone())
+                        .map_err(|err| WorkError::from_add(err, self.context()))?;
+                    self.work_done = now;
+                    return Ok(amount);
+                }
+            }
+            // Just clamp to maximum work possible.
+            let accepted = limit.clone() - self.work_done.clone();
+            self.work_done = limit;
+            Ok(accepted)
+        } else {
+            self.work_done = now;
+            Ok(amount)
+        }
+    }
+
+    /// Takes back work which was completed. Returns the amount of work taken back.
+    pub(crate) fn try_unworked(&mut self, amount: W) -> Result<W, WorkError> {
+        if let Some(Ok(_)) = self.closed {
+            return Err(WorkError::Closed {
+                monitor: Box::new(self.context()),
+            });
+        }
+        if !matches!(
+            amount.partial_cmp(&self.work_done),
+            Some(Ordering::Less | Ordering::Equal)
+        ) {
+            return Err(WorkError::NotCompleted {
+                monitor: Box::new(self.context()),
+                submitted: amount.to_string(),
+            });
+        }
+        self.work_done = self.work_done.clone() - amount.clone();
+        self.publish();
+        Ok(amount)
+    }
+
+    /// Replaces the total amount of work, which must cover the work already completed.
+    /// An indeterminate monitor becomes determinate.
+    pub(crate) fn try_set_total(&mut self, total: W) -> Result<(), WorkError> {
+        if let Some(Ok(_)) = self.closed {
+            return Err(WorkError::Closed {
+                monitor: Box::new(self.context()),
+            });
+        }
+        if !matches!(
+            self.work_done.partial_cmp(&total),
+            Some(Ordering::Less | Ordering::Equal)
+        ) {
+            return Err(WorkError::TotalBelowCompleted {
+                monitor: Box::new(self.context()),
+                total: total.to_string(),
+            });
+        }
+        self.work = total;
+        self.indeterminate = false;
+        self.publish();
+        Ok(())
+    }
+
+    /// Remembers the work of this monitor, to return to it if work cannot be forwarded to the parent of this monitor.
+    pub(crate) fn checkpoint(&self) -> Checkpoint<W> {
+        Checkpoint {
+            work: self.work.clone(),
+            work_done: self.work_done.clone(),
+            indeterminate: self.indeterminate,
+        }
+    }
+
+    /// Returns to the work remembered in `checkpoint`, notifying subscribers.
+    pub(crate) fn restore(&mut self, checkpoint: Checkpoint<W>) {
+        self.work = checkpoint.work;
+        self.work_done = checkpoint.work_done;
+        self.indeterminate = checkpoint.indeterminate;
+        self.publish();
+    }
+
+    pub(crate) fn pause(&mut self) {
+        if self.clock.pause() {
+            self.publish();
+        }
+    }
+
+    pub(crate) fn resume(&mut self) {
+        if self.clock.resume() {
+            self.publish();
+        }
+    }
+
+    /// Zero while indeterminate, as there is no work known to be left.
+    pub(crate) fn remaining(&self) -> W {
+        match self.indeterminate {
+            true => W::zero(),
+            false => self.work.clone() - self.work_done.clone(),
+        }
+    }
+
+    pub(crate) fn is_finished(&self) -> bool {
+        !self.indeterminate && self.work_done == self.work
+    }
+
+    /// Closes this monitor as skipped, independent of the work left.
+    pub(crate) fn skip(&mut self, reason: String) -> CloseOutcome {
+        if let Some(Ok(outcome)) = &self.closed {
+            return outcome.clone();
+        }
+        let outcome = CloseOutcome::Skipped { reason };
+        self.clock.stop();
+        self.closed = Some(Ok(outcome.clone()));
+        self.publish();
+        outcome
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        matches!(self.closed, Some(Ok(_)))
+    }
+
+    /// Closes this monitor. Closing fails if there is work left, unless the monitor was cancelled.
+    /// Indeterminate monitors have no work left and always complete.
+    /// A monitor which was already closed successfully stays closed.
+    pub(crate) fn close(&mut self) -> Result<CloseOutcome, CloseError> {
+        if let Some(Ok(outcome)) = &self.closed {
+            return Ok(outcome.clone());
+        }
+        let result = if self.remaining() == W::zero() {
+            Ok(CloseOutcome::Completed)
+        } else if self.token.is_cancelled() {
+            Ok(CloseOutcome::Cancelled)
+        } else {
+            Err(CloseError::Incomplete {
+                monitor: Box::new(self.context()),
+            })
+        };
+        if result.is_ok() {
+            self.clock.stop();
+        }
+        self.closed = Some(result.clone());
+        self.publish();
+        result
+    }
+}
+
+/// The work of a monitor at some point. See `MonitorState::checkpoint`.
+#[derive(Debug)]
+pub(crate) struct Checkpoint<W: Work> {
+    work: W,
+    work_done: W,
+    indeterminate: bool,
+}
+
+/// Tracks the amount of parent work a child monitor is responsible for
+/// and how much of it was already submitted to the parent.
+///
+/// The parent work submitted is derived from the work done in the child as a whole, not from each amount of work on its own.
+/// This way, rounding errors never accumulate and many small amounts of work in the child still advance the parent.
+#[derive(Debug, Clone)]
+pub(crate) struct ParentShare<W: Work> {
+    /// Tells how much work of the parent is handled by the child.
+    pub(crate) parent_work: W,
+    /// Tracks the amount of work submitted to the parent. Must equal `parent_work` when closing the child!
+    pub(crate) parent_work_submitted: W,
+    /// Set once the total of the child changed or work was taken back.
+    rescaled: Option<Segment<W>>,
+}
+
+/// Maps the work a child had left at some point onto the parent work which was not yet submitted at that point.
+#[derive(Debug, Clone)]
+struct Segment<W: Work> {
+    child_done: W,
+    child_work: W,
+    parent_submitted: W,
+    parent_work: W,
+}
+
+impl<W: Work> ParentShare<W> {
+    pub(crate) fn new(parent_work: W) -> Self {
+        Self {
+            parent_work,
+            parent_work_submitted: W::zero(),
+            rescaled: None,
+        }
+    }
+
+    /// The parent work which corresponds to the work done in `child`.
+    fn target(&self, child: &MonitorState<'_, W>) -> Result<W, WorkError> {
+        let done = child.work_done.clone();
+        match &self.rescaled {
+            None => Ok(W::parent_work_done_when(
+                done,
+                child.work.clone(),
+                self.parent_work.clone(),
+            )),
+            Some(segment)
+                if matches!(
+                    done.partial_cmp(&segment.child_done),
+                    Some(Ordering::Greater | Ordering::Equal)
+                ) =>
+            {
+                let parent_done = W::parent_work_done_when(
+                    done - segment.child_done.clone(),
+                    segment.child_work.clone(),
+                    segment.parent_work.clone(),
+                );
+                (segment.parent_submitted.clone() + parent_done)
+                    .map_err(|err| WorkError::from_add(err, child.context()))
+            }
+            // Work done before the segment started was taken back.
+            Some(segment) => Ok(W::parent_work_done_when(
+                done,
+                segment.child_done.clone(),
+                segment.parent_submitted.clone(),
+            )),
+        }
+    }
+
+    /// Starts a new segment, mapping the work `child` has left onto the parent work not yet submitted.
+    fn rebase(&mut self, child: &MonitorState<'_, W>) {
+        self.rescaled = Some(Segment {
+            child_done: child.work_done.clone(),
+            child_work: child.remaining(),
+            parent_submitted: self.parent_work_submitted.clone(),
+            parent_work: self.parent_work.clone() - self.parent_work_submitted.clone(),
+        });
+    }
+
+    /// Runs `f`, undoing all its changes to this share if it fails, e.g. because the parent rejected the work.
+    pub(crate) fn atomically(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<(), WorkError>,
+    ) -> Result<(), WorkError> {
+        let previous = self.clone();
+        let result = f(self);
+        if result.is_err() {
+            *self = previous;
+        }
+        result
+    }
+
+    /// Computes the amount of parent work which must be submitted to the parent, after `child` accepted work.
+    /// An indeterminate child submits nothing before it is closed.
+    pub(crate) fn advance(&mut self, child: &MonitorState<'_, W>) -> Result<W, WorkError> {
+        let parent_worked = if child.indeterminate {
+            W::zero()
+        } else if !child.is_finished() {
+            // If the child monitor is not yet finished, we submit whatever the parent lacks behind the child.
+            let target = self.target(child)?;
+            match target.partial_cmp(&self.parent_work_submitted) {
+                Some(Ordering::Greater) => target - self.parent_work_submitted.clone(),
+                _ => W::zero(),
+            }
+        } else {
+            // If the child monitor did all its work, we dispatch all the remaining parent work.
+            // Why? We advance the parent work with relative work done.
+            // Based on the actual work type W, this might only be computable with a loss of precision.
+            // For example by truncating floating point data.
+            // This may result in us not advancing the parent progress enough, so we simply push the remaining work.
+            self.parent_work.clone() - self.parent_work_submitted.clone()
+        };
+        self.parent_work_submitted = (self.parent_work_submitted.clone() + parent_worked.clone())
+            .map_err(|err| WorkError::from_add(err, child.context()))?;
+        Ok(parent_worked)
+    }
+
+    /// Computes the amount of parent work which must be taken back from the parent, after `child` took back work.
+    pub(crate) fn retreat(&mut self, child: &MonitorState<'_, W>) -> Result<W, WorkError> {
+        if child.indeterminate {
+            return Ok(W::zero());
+        }
+        if child.work_done == W::zero() {
+            self.rescaled = None;
+            return Ok(std::mem::replace(
+                &mut self.parent_work_submitted,
+                W::zero(),
+            ));
+        }
+        let target = self.target(child)?;
+        let parent_taken = match self.parent_work_submitted.partial_cmp(&target) {
+            Some(Ordering::Greater) => self.parent_work_submitted.clone() - target,
+            _ => W::zero(),
+        };
+        self.parent_work_submitted = self.parent_work_submitted.clone() - parent_taken.clone();
+        // Finishing the child must still submit exactly its parent work.
+        self.rebase(child);
+        Ok(parent_taken)
+    }
+
+    /// Computes the parent work not yet submitted, e.g. when skipping the child or closing an indeterminate one.
+    /// It counts as submitted afterwards.
+    pub(crate) fn rest(&mut self) -> W {
+        let rest = self.parent_work.clone() - self.parent_work_submitted.clone();
+        self.parent_work_submitted = self.parent_work.clone();
+        rest
+    }
+
+    /// Must be called after the total of `child` changed.
+    /// Returns the parent work which must be submitted to the parent, if the child is finished under its new total.
+    pub(crate) fn rescale(&mut self, child: &MonitorState<'_, W>) -> Result<Option<W>, WorkError> {
+        if child.is_finished() {
+            return self.advance(child).map(Some);
+        }
+        self.rebase(child);
+        Ok(None)
+    }
+}
@@ -3,138 +3,191 @@ use std::{
     fmt::{Debug, Display},
 };
 
-use crate::{work::Work, CloseError};
+use crate::{error::WorkError, work::Work};
 
-use super::{ProgressMonitor, ProgressMonitorDivision};
+use super::{
+    cancel::CancellationToken,
+    clock::Clock,
+    config::{handle_drop, MonitorConfig},
+    state::{MonitorState, ParentShare},
+    subscription::Subscription,
+    CloseOutcome, ProgressMonitor, ProgressMonitorDivision,
+};
 
 /// A child monitor references a parent monitor.
 /// It monitors a subset of it's parent's total work, named `parent_work`.
 /// This monitors own scale is declared by `sub_work` and can be arbitrary.
-/// A child monitor, as a main monitor, tracks its progress. See `sub_work_completed`.
+/// A child monitor, as a main monitor, tracks its progress.
 /// Whenever work is being made in a child monitor, the parents progress is increased relative amount.
 ///
 /// Example:
 /// Given a ChildMonitor with
 /// - parent_work == 10
 /// - sub_work == 1000
+///
 /// When a work(500) is submitted
 /// Then the parent.work(5) is submitted.
 #[derive(Debug)]
 pub struct ChildMonitor<'n, 'p, W: Work, P: ProgressMonitor<W>> {
-    name: Cow<'n, str>,
     /// A reference to the parent progress monitor. It must be mutable. When we do some work, our parent must also advance.
     parent: &'p mut P,
-    /// Tells how much work of the parent is handled by this child.
-    parent_work: W,
-    /// Provides a new scale to work with. If this work is completed, the parent_work is completed.
-    sub_work: W,
-    /// How much sub_work was completed.
-    sub_work_completed: W,
-    /// Tracks thr amount of work submitted to the parent. Must equal `parent_work` when closing this child monitor!
-    parent_work_submitted: W,
-    closed: Option<Result<(), CloseError>>,
+    /// Tracks the `sub_work` of this monitor. Provides a new scale to work with. If this work is completed, the parent_work is completed.
+    state: MonitorState<'n, W>,
+    /// Tracks how much work of the parent is handled by this child and how much of it was already submitted.
+    share: ParentShare<W>,
 }
 
 impl<'n, 'p, W: Work, P: ProgressMonitor<W>> ChildMonitor<'n, 'p, W, P> {
     pub fn new(name: Cow<'n, str>, parent: &'p mut P, parent_work: W, sub_work: W) -> Self {
         Self {
-            name,
+            state: MonitorState::new(name, sub_work).child_of(&*parent),
+            parent,
+            share: ParentShare::new(parent_work),
+        }
+    }
+
+    /// A child with an unknown total. See `ProgressMonitorDivision::new_indeterminate_child`.
+    pub fn new_indeterminate(name: Cow<'n, str>, parent: &'p mut P, parent_work: W) -> Self {
+        Self {
+            state: MonitorState::new(name, W::zero())
+                .child_of(&*parent)
+                .indeterminate(),
             parent,
-            parent_work,
-            sub_work,
-            sub_work_completed: W::zero(),
-            parent_work_submitted: W::zero(),
-            closed: None,
+            share: ParentShare::new(parent_work),
         }
     }
 
     pub fn name(&self) -> Cow<'n, str> {
-        self.name.clone()
+        self.state.name.clone()
+    }
+
+    /// Overrides the configuration inherited from the parent.
+    pub fn with_config(mut self, config: MonitorConfig) -> Self {
+        self.state.config = config;
+        self
+    }
+
+    /// Observe the progress of this monitor from somewhere else, e.g. another thread.
+    pub fn subscribe(&mut self) -> Subscription<W> {
+        self.state.subscribe()
     }
 }
 
 impl<'n, 'p, W: Work, P: ProgressMonitor<W>> ProgressMonitor<W> for ChildMonitor<'n, 'p, W, P> {
-    fn worked<A: Into<W>>(&mut self, amount_of_work: A) {
-        let amount_of_work: W = amount_of_work.into();
-
-        // Advance the work we have done, while preventing overshooting.
-        let now: W = (self.sub_work_completed.clone() + amount_of_work.clone()).unwrap();
-        if now > self.sub_work {
-            // Would overshoot! Just clamp to maximum work possible.
-            // TODO: Control overshoot behavior through monitor configuration.
-            tracing::warn!(
-                work = ?self.sub_work,
-                work_done = ?self.sub_work_completed,
-                new_work_done = ?amount_of_work,
-                would_become = ?now,
-                "Detected overshoot. Try to only submit work left open. Ignoring additional work."
-            );
-            self.sub_work_completed = self.sub_work.clone();
-        } else {
-            self.sub_work_completed = now;
-        }
-
-        let finished = self.sub_work_completed == self.sub_work;
-
-        // We have to advance our parent work.
-        if !finished {
-            // If this child monitor is not yet finished, we can dispatch parent work normally.
-            // There is the possibility that the given work was more than our total sub work. We have to catch that and clamp.
-            let amount_of_work: W = W::min(&amount_of_work, &self.sub_work).clone();
-            let parent_worked: W = W::parent_work_done_when(
-                amount_of_work,
-                self.sub_work.clone(),
-                self.parent_work.clone(),
-            );
-            self.parent.worked(parent_worked.clone());
-            let new_parent_work_submitted =
-                W::add(self.parent_work_submitted.clone(), parent_worked)
-                    .unwrap()
-                    .clone();
-            self.parent_work_submitted = new_parent_work_submitted;
-        } else {
-            // If this child monitor did all its work, we dispatch all the remaining parent work.
-            // Why? We advance the parent work with relative work done.
-            // Based on the actual work type W, this might only be computable with a loss of precision.
-            // For example by truncating floating point data.
-            // This may result in us not advancing the parent progress enough, so we simply push the remaining work.
-            let remaining_parent_work =
-                self.parent_work.clone() - self.parent_work_submitted.clone();
-            self.parent.worked(remaining_parent_work.clone());
-            let new_parent_work_submitted =
-                W::add(self.parent_work_submitted.clone(), remaining_parent_work)
-                    .unwrap()
-                    .clone();
-            self.parent_work_submitted = new_parent_work_submitted;
+    fn name(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.state.name)
+    }
+
+    fn try_worked<A: Into<W>>(&mut self, amount_of_work: A) -> Result<(), WorkError> {
+        let checkpoint = self.state.checkpoint();
+        self.state.try_worked(amount_of_work.into())?;
+
+        // We have to advance our parent work. Work our parent rejects is rejected by us as well.
+        let (state, parent) = (&self.state, &mut *self.parent);
+        let forwarded = self
+            .share
+            .atomically(|share| parent.try_worked(share.advance(state)?));
+        if forwarded.is_err() {
+            self.state.restore(checkpoint);
+        }
+        forwarded
+    }
+
+    fn try_unworked<A: Into<W>>(&mut self, amount_of_work: A) -> Result<(), WorkError> {
+        let checkpoint = self.state.checkpoint();
+        self.state.try_unworked(amount_of_work.into())?;
+
+        // Our parent must lose the work we contributed.
+        let (state, parent) = (&self.state, &mut *self.parent);
+        let forwarded = self
+            .share
+            .atomically(|share| parent.try_unworked(share.retreat(state)?));
+        if forwarded.is_err() {
+            self.state.restore(checkpoint);
         }
+        forwarded
     }
 
-    fn total(&self) -> &W {
-        &self.sub_work
+    fn total(&self) -> Cow<'_, W> {
+        Cow::Borrowed(&self.state.work)
     }
 
-    fn completed(&self) -> &W {
-        &self.sub_work_completed
+    fn try_set_total<A: Into<W>>(&mut self, total: A) -> Result<(), WorkError> {
+        let checkpoint = self.state.checkpoint();
+        self.state.try_set_total(total.into())?;
+        let (state, parent) = (&self.state, &mut *self.parent);
+        let forwarded = self.share.atomically(|share| match share.rescale(state)? {
+            Some(parent_worked) => parent.try_worked(parent_worked),
+            None => Ok(()),
+        });
+        if forwarded.is_err() {
+            self.state.restore(checkpoint);
+        }
+        forwarded
     }
 
-    fn remaining(&self) -> Cow<W> {
-        Cow::Owned(self.sub_work.clone() - self.sub_work_completed.clone())
+    fn completed(&self) -> Cow<'_, W> {
+        Cow::Borrowed(&self.state.work_done)
     }
 
-    fn close(&mut self) -> Result<(), crate::CloseError> {
-        if self.closed.is_none() {
-            let work_left = self.remaining();
-            let result = if work_left.as_ref() == &W::zero() {
-                Ok(())
-            } else {
-                Err(crate::CloseError { msg: format!("Must not close progress monitor {self:#?} when work left is {work_left} which is != 0.") })
-            };
-            self.closed = Some(result.clone()); // Clone is ok, as our happy path is Copy.
-            result
-        } else {
-            // TODO: Forbid multiple closes?
-            self.closed.clone().unwrap()
+    fn remaining(&self) -> Cow<'_, W> {
+        Cow::Owned(self.state.remaining())
+    }
+
+    fn close(&mut self) -> Result<CloseOutcome, crate::CloseError> {
+        let indeterminate = self.state.indeterminate;
+        let result = self.state.close();
+        if indeterminate && result == Ok(CloseOutcome::Completed) {
+            // Not knowing how far along the child was, its parent work is credited as a whole.
+            let parent_worked = self.share.rest();
+            if parent_worked != W::zero() {
+                self.parent.worked(parent_worked);
+            }
         }
+        result
+    }
+
+    fn pause(&mut self) {
+        self.state.pause()
+    }
+
+    fn resume(&mut self) {
+        self.state.resume()
+    }
+
+    fn clock(&self) -> Clock {
+        self.state.clock
+    }
+
+    fn skip<R: Into<String>>(&mut self, reason: R) -> CloseOutcome {
+        if !self.state.is_closed() {
+            let parent_worked = self.share.rest();
+            if parent_worked != W::zero() {
+                self.parent.worked(parent_worked);
+            }
+        }
+        self.state.skip(reason.into())
+    }
+
+    fn is_indeterminate(&self) -> bool {
+        self.state.indeterminate
+    }
+
+    fn cancellation_token(&self) -> CancellationToken {
+        self.state.token.clone()
+    }
+
+    fn config(&self) -> MonitorConfig {
+        self.state.config
+    }
+
+    fn path(&self) -> Vec<String> {
+        self.state.path.clone()
+    }
+
+    #[cfg(feature = "registry")]
+    fn registry_id(&self) -> Option<crate::registry::MonitorId> {
+        self.state.registration.id()
     }
 }
 
@@ -148,52 +201,191 @@ where
     A2: Into<W>,
     P: ProgressMonitor<W>,
 {
-    fn new_child(
-        &'p mut self,
-        name: N,
-        amount_of_parent_work: A1,
-        amount_of_child_work: A2,
-    ) -> ChildMonitor<'n, 'p, W, Self> {
-        let amount_of_parent_work: W = amount_of_parent_work.into();
-        let amount_of_child_work: W = amount_of_child_work.into();
-
-        // TODO: As Result?
-        assert!(&amount_of_parent_work <= self.remaining().as_ref());
-
-        ChildMonitor::new(
-            name.into(),
-            self,
-            amount_of_parent_work,
-            amount_of_child_work,
-        )
-    }
 }
 
 impl<'n, 'p, W: Work, T: ProgressMonitor<W>> Display for ChildMonitor<'n, 'p, W, T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_fmt(format_args!(
-            "{}/{}",
-            self.sub_work_completed, self.sub_work
-        ))
+        match self.state.indeterminate {
+            true => f.write_fmt(format_args!("{}/?", self.state.work_done)),
+            false => f.write_fmt(format_args!("{}/{}", self.state.work_done, self.state.work)),
+        }
     }
 }
 
 impl<'n, 'p, W: Work, T: ProgressMonitor<W>> Drop for ChildMonitor<'n, 'p, W, T> {
     fn drop(&mut self) {
-        match &self.closed {
-            Some(result) => match result {
-                Ok(()) => { /* do nothing */ }
-                Err(err) => {
-                    tracing::error!(
-                        "SubMonitor was not successfully closed. Reason: {}",
-                        err.msg
-                    );
+        let closed = self.state.closed.clone();
+        handle_drop(self, closed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+
+    #[test]
+    fn changing_the_total_rescales_parent_work() {
+        let mut root = CallbackProgressMonitor::silent("root", 100);
+        let mut subscription = root.subscribe();
+        {
+            let mut child = root.new_child("child", 50u64, 10u64);
+            child.worked(5u64);
+            child.add_total(10u64);
+            assert_eq!(child.total().as_ref(), &NumericWork::new(20u64));
+            assert!(matches!(
+                child.try_set_total(4u64),
+                Err(WorkError::TotalBelowCompleted { .. })
+            ));
+
+            // The remaining 15 child work now maps onto the remaining 25 parent work.
+            child.worked(3u64);
+            assert_eq!(subscription.latest().completed, NumericWork::new(30u64));
+            child.worked(12u64);
+            child.close().unwrap();
+        }
+        assert_eq!(root.completed().as_ref(), &NumericWork::new(50u64));
+        {
+            let mut child = root.new_child("shrinking", 50u64, 10u64);
+            child.worked(4u64);
+            child.set_total(4u64);
+            child.close().unwrap();
+        }
+        root.close().unwrap();
+    }
+
+    #[test]
+    fn failed_attempts_are_rolled_back_in_parent() {
+        let reported = std::cell::RefCell::new(Vec::new());
+        let mut root = CallbackProgressMonitor::new(
+            "root",
+            100u64,
+            |_: &NumericWork<u64>, w: &NumericWork<u64>| reported.borrow_mut().push(*w.value()),
+        );
+        {
+            let mut child = root.new_child("step", 50u64, 10u64);
+            let mut attempts = 0;
+            let result: Result<(), &str> = child.retry(3, |child| {
+                attempts += 1;
+                child.worked(4u64);
+                if attempts == 1 {
+                    return Err("flaky");
                 }
-            },
-            None => {
-                tracing::warn!("close() was not called on {self:?}!");
-                self.close().expect("Successful close");
+                child.worked(6u64);
+                Ok(())
+            });
+            assert_eq!(result, Ok(()));
+            assert_eq!(attempts, 2);
+            child.close().unwrap();
+        }
+        assert_eq!(*reported.borrow(), [20, 0, 20, 50]);
+
+        root.worked(10u64);
+        root.reset();
+        assert_eq!(root.completed().as_ref(), &NumericWork::new(0u64));
+        assert!(matches!(
+            root.try_unworked(1u64),
+            Err(WorkError::NotCompleted { .. })
+        ));
+
+        // Work taken back by a failed attempt stays taken back.
+        root.worked(5u64);
+        let result: Result<(), ()> = root.attempt(|root| {
+            root.reset();
+            Err(())
+        });
+        assert_eq!(result, Err(()));
+        assert_eq!(root.completed().as_ref(), &NumericWork::new(0u64));
+        root.worked(100u64);
+        root.close().unwrap();
+    }
+
+    #[test]
+    fn skipped_children_hand_over_their_parent_work() {
+        let mut root = CallbackProgressMonitor::silent("root", 100);
+        {
+            let mut child = root.new_child("cached", 40u64, 10u64);
+            child.worked(2u64);
+            let outcome = child.skip("Found in cache");
+            assert_eq!(
+                outcome,
+                CloseOutcome::Skipped {
+                    reason: "Found in cache".to_owned()
+                }
+            );
+            assert_eq!(child.completed().as_ref(), &NumericWork::new(2u64));
+            assert_eq!(child.close(), Ok(outcome));
+        }
+        assert_eq!(root.completed().as_ref(), &NumericWork::new(40u64));
+        {
+            let mut child = root.new_child("rest", 60u64, 10u64);
+            child.worked(2u64);
+            assert_eq!(child.finish(), Ok(CloseOutcome::Completed));
+        }
+        assert_eq!(root.finish(), Ok(CloseOutcome::Completed));
+    }
+
+    #[test]
+    fn parent_progress_is_smooth() {
+        let mut root = CallbackProgressMonitor::silent("root", 100);
+        let mut subscription = root.subscribe();
+        {
+            let mut child = root.new_child("child", 100u64, 5000u64);
+            for done in 1..=5000u64 {
+                child.worked(1u64);
+                assert_eq!(
+                    subscription.latest().completed,
+                    NumericWork::new(done * 100 / 5000)
+                );
             }
+            child.close().unwrap();
+        }
+        root.close().unwrap();
+    }
+
+    #[test]
+    fn indeterminate_children_are_credited_on_close() {
+        let mut root = CallbackProgressMonitor::silent("root", 100);
+        let mut subscription = root.subscribe();
+        assert!(root.try_new_indeterminate_child("crawl", 200u64).is_err());
+        {
+            let mut crawl = root.new_indeterminate_child("crawl", 40u64);
+            crawl.worked(1000u64);
+            assert!(crawl.is_indeterminate());
+            assert_eq!(crawl.to_string(), "1000/?");
+            assert_eq!(crawl.remaining().as_ref(), &NumericWork::zero());
+            assert_eq!(crawl.close(), Ok(CloseOutcome::Completed));
+        }
+        assert_eq!(root.completed().as_ref(), &NumericWork::new(40u64));
+        {
+            let mut stream = root.new_indeterminate_child("stream", 60u64);
+            stream.worked(10u64);
+            stream.set_total(40u64);
+            assert!(!stream.is_indeterminate());
+            stream.worked(15u64);
+            assert_eq!(subscription.latest().completed, NumericWork::new(70u64));
+            assert!(stream.close().is_err());
+            stream.finish().unwrap();
+        }
+        assert_eq!(root.finish(), Ok(CloseOutcome::Completed));
+    }
+
+    #[test]
+    fn set_children_advance_their_parent_step_by_step() {
+        let mut root = CallbackProgressMonitor::new(
+            "root",
+            &[1u8, 2, 3, 4],
+            |_: &SetWork<u8>, _: &SetWork<u8>| {},
+        );
+        {
+            let mut child = root.new_child("child", &[1u8, 2], &[10u8, 11, 12]);
+            child.try_worked(10u8).unwrap();
+            assert_eq!(child.completed().as_ref(), &SetWork::from(10u8));
+            child.try_worked(11u8).unwrap();
+            child.try_worked(12u8).unwrap();
+            child.close().unwrap();
         }
+        assert_eq!(root.completed().as_ref(), &SetWork::from(&[1u8, 2]));
+        root.worked(SetWork::from(&[3u8, 4]));
+        root.close().unwrap();
     }
 }
@@ -0,0 +1,236 @@
+use std::{
+    fmt::Debug,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError, Weak},
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use crate::{work::Work, CloseError};
+
+use super::CloseOutcome;
+
+/// The state of a monitor at some point in time.
+#[derive(Debug, Clone)]
+pub struct ProgressSnapshot<W: Work> {
+    pub name: String,
+    pub total: W,
+    pub completed: W,
+    /// `None` as long as the monitor was not closed. The result of closing the monitor otherwise.
+    pub closed: Option<Result<CloseOutcome, CloseError>>,
+    /// Whether the monitor was paused.
+    pub paused: bool,
+    /// Whether the total is unknown. `total` is zero then. Show activity instead of a ratio.
+    pub indeterminate: bool,
+    /// The time the monitor was active, leaving out the time it was paused. Use it for rate or ETA calculations.
+    pub active_time: Duration,
+}
+
+#[derive(Debug)]
+struct Slot<W: Work> {
+    snapshot: ProgressSnapshot<W>,
+    /// Incremented with every published snapshot.
+    version: u64,
+    /// Whether the monitor publishing into this slot still exists.
+    publisher_alive: bool,
+    wakers: Vec<Waker>,
+}
+
+#[derive(Debug)]
+struct Channel<W: Work> {
+    slot: Mutex<Slot<W>>,
+    changed: Condvar,
+}
+
+impl<W: Work> Channel<W> {
+    fn lock(&self) -> MutexGuard<'_, Slot<W>> {
+        self.slot.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn notify(&self, mut slot: MutexGuard<'_, Slot<W>>) {
+        let wakers = std::mem::take(&mut slot.wakers);
+        drop(slot);
+        self.changed.notify_all();
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+/// Observes the progress of a monitor without being its owner. Created through the `subscribe` method of a monitor.
+///
+/// Only the latest snapshot is kept. Observers not keeping up with the progress made skip intermediate snapshots.
+/// Changes can be awaited, either blocking using `wait_for_change` (or by iterating) or asynchronously using `changed`.
+/// Once the monitor is gone, no further changes are reported.
+#[derive(Debug)]
+pub struct Subscription<W: Work> {
+    channel: Arc<Channel<W>>,
+    seen: u64,
+}
+
+impl<W: Work> Subscription<W> {
+    /// Get the latest snapshot, whether it was seen before or not.
+    pub fn latest(&mut self) -> ProgressSnapshot<W> {
+        let slot = self.channel.lock();
+        self.seen = slot.version;
+        slot.snapshot.clone()
+    }
+
+    /// Get the latest snapshot, if it was not yet seen.
+    pub fn try_changed(&mut self) -> Option<ProgressSnapshot<W>> {
+        let slot = self.channel.lock();
+        if slot.version == self.seen {
+            return None;
+        }
+        self.seen = slot.version;
+        Some(slot.snapshot.clone())
+    }
+
+    /// Block until a new snapshot is available.
+    /// Returns `None` if the monitor is gone and no unseen snapshot is left.
+    pub fn wait_for_change(&mut self) -> Option<ProgressSnapshot<W>> {
+        let mut slot = self.channel.lock();
+        while slot.version == self.seen {
+            if !slot.publisher_alive {
+                return None;
+            }
+            slot = self
+                .channel
+                .changed
+                .wait(slot)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        self.seen = slot.version;
+        Some(slot.snapshot.clone())
+    }
+
+    /// Wait for a new snapshot without blocking the current thread.
+    /// Resolves to `None` if the monitor is gone and no unseen snapshot is left.
+    pub fn changed(&mut self) -> Changed<'_, W> {
+        Changed { subscription: self }
+    }
+}
+
+impl<W: Work> Iterator for Subscription<W> {
+    type Item = ProgressSnapshot<W>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.wait_for_change()
+    }
+}
+
+/// Future returned by `Subscription::changed`.
+#[derive(Debug)]
+pub struct Changed<'s, W: Work> {
+    subscription: &'s mut Subscription<W>,
+}
+
+impl<'s, W: Work> Future for Changed<'s, W> {
+    type Output = Option<ProgressSnapshot<W>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let subscription = &mut *self.get_mut().subscription;
+        let mut slot = subscription.channel.lock();
+        if slot.version != subscription.seen {
+            subscription.seen = slot.version;
+            return Poll::Ready(Some(slot.snapshot.clone()));
+        }
+        if !slot.publisher_alive {
+            return Poll::Ready(None);
+        }
+        if !slot.wakers.iter().any(|it| it.will_wake(cx.waker())) {
+            slot.wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+/// The publishing end of all subscriptions of a monitor.
+pub(crate) struct Subscribers<W: Work> {
+    channels: Vec<Weak<Channel<W>>>,
+}
+
+impl<W: Work> Subscribers<W> {
+    pub(crate) fn new() -> Self {
+        Self {
+            channels: Vec::new(),
+        }
+    }
+
+    pub(crate) fn subscribe(&mut self, snapshot: ProgressSnapshot<W>) -> Subscription<W> {
+        let channel = Arc::new(Channel {
+            slot: Mutex::new(Slot {
+                snapshot,
+                version: 0,
+                publisher_alive: true,
+                wakers: Vec::new(),
+            }),
+            changed: Condvar::new(),
+        });
+        self.channels.push(Arc::downgrade(&channel));
+        Subscription { channel, seen: 0 }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.channels.is_empty()
+    }
+
+    /// Publish a new snapshot to all subscriptions still alive.
+    pub(crate) fn publish(&mut self, snapshot: ProgressSnapshot<W>) {
+        self.channels.retain(|channel| match channel.upgrade() {
+            Some(channel) => {
+                let mut slot = channel.lock();
+                slot.snapshot = snapshot.clone();
+                slot.version += 1;
+                channel.notify(slot);
+                true
+            }
+            None => false,
+        });
+    }
+}
+
+impl<W: Work> Debug for Subscribers<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Subscribers")
+            .field("count", &self.channels.len())
+            .finish()
+    }
+}
+
+impl<W: Work> Drop for Subscribers<W> {
+    fn drop(&mut self) {
+        for channel in self.channels.drain(..) {
+            if let Some(channel) = channel.upgrade() {
+                let mut slot = channel.lock();
+                slot.publisher_alive = false;
+                channel.notify(slot);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn subscription_observes_progress() {
+        let mut mon = CallbackProgressMonitor::silent("root", 10);
+        let subscription = mon.subscribe();
+        let observer = thread::spawn(move || subscription.last());
+
+        mon.worked(4u64);
+        mon.worked(6u64);
+        mon.close().unwrap();
+        drop(mon);
+
+        let last = observer.join().unwrap().unwrap();
+        assert_eq!(last.name, "root");
+        assert_eq!(last.completed, NumericWork::new(10u64));
+        assert!(matches!(last.closed, Some(Ok(_))));
+    }
+}
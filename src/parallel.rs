@@ -0,0 +1,149 @@
+//! Integration with rayon's parallel iterators. Requires the `rayon` feature.
+
+use rayon::iter::{
+    plumbing::{Consumer, ProducerCallback, UnindexedConsumer},
+    IndexedParallelIterator, ParallelIterator,
+};
+
+use crate::{
+    monitor::{atomic::AtomicProgressMonitor, shared::SharedProgressMonitor},
+    work::{numeric::NumericWork, Work},
+};
+
+/// A monitor to which work can be submitted concurrently, through a shared reference.
+pub trait ConcurrentProgressMonitor: Sync {
+    /// Submit a single unit of work.
+    fn item_done(&self);
+
+    /// Use `items` as the total amount of work, if the monitor has no total yet. See `AtomicProgressMonitor::infer_total`.
+    fn infer_total(&self, items: usize);
+}
+
+impl<'n, C> ConcurrentProgressMonitor for AtomicProgressMonitor<'n, C>
+where
+    C: Fn(&NumericWork<u64>, &NumericWork<u64>) + Sync,
+{
+    fn item_done(&self) {
+        self.add(1);
+    }
+
+    fn infer_total(&self, items: usize) {
+        AtomicProgressMonitor::infer_total(self, items as u64);
+    }
+}
+
+impl<'n> ConcurrentProgressMonitor for SharedProgressMonitor<'n, NumericWork<u64>> {
+    fn item_done(&self) {
+        self.work(NumericWork::new(1u64));
+    }
+
+    fn infer_total(&self, items: usize) {
+        SharedProgressMonitor::infer_total(self, items as u64);
+    }
+}
+
+/// Adds progress monitoring to parallel iterators.
+pub trait ParallelProgressIterator: ParallelIterator {
+    /// Submit one unit of work to `monitor` for every item processed.
+    ///
+    /// If the iterator knows its length, as an `IndexedParallelIterator` does,
+    /// that length is used as the total amount of work of a monitor created without a total.
+    fn with_progress<M: ConcurrentProgressMonitor>(self, monitor: &M) -> ProgressIter<'_, Self, M> {
+        ProgressIter {
+            base: self,
+            monitor,
+        }
+    }
+}
+
+impl<I: ParallelIterator> ParallelProgressIterator for I {}
+
+/// A parallel iterator submitting work to a monitor for every item processed. See `ParallelProgressIterator`.
+#[derive(Debug)]
+#[must_use = "iterator adaptors are lazy and do nothing unless consumed"]
+pub struct ProgressIter<'m, I, M> {
+    base: I,
+    monitor: &'m M,
+}
+
+impl<'m, I, M> ParallelIterator for ProgressIter<'m, I, M>
+where
+    I: ParallelIterator,
+    M: ConcurrentProgressMonitor,
+{
+    type Item = I::Item;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        if let Some(len) = self.base.opt_len() {
+            self.monitor.infer_total(len);
+        }
+        let monitor = self.monitor;
+        self.base
+            .inspect(move |_| monitor.item_done())
+            .drive_unindexed(consumer)
+    }
+
+    fn opt_len(&self) -> Option<usize> {
+        self.base.opt_len()
+    }
+}
+
+impl<'m, I, M> IndexedParallelIterator for ProgressIter<'m, I, M>
+where
+    I: IndexedParallelIterator,
+    M: ConcurrentProgressMonitor,
+{
+    fn drive<C: Consumer<Self::Item>>(self, consumer: C) -> C::Result {
+        self.monitor.infer_total(self.base.len());
+        let monitor = self.monitor;
+        self.base
+            .inspect(move |_| monitor.item_done())
+            .drive(consumer)
+    }
+
+    fn len(&self) -> usize {
+        self.base.len()
+    }
+
+    fn with_producer<CB: ProducerCallback<Self::Item>>(self, callback: CB) -> CB::Output {
+        self.monitor.infer_total(self.base.len());
+        let monitor = self.monitor;
+        self.base
+            .inspect(move |_| monitor.item_done())
+            .with_producer(callback)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rayon::prelude::*;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn total_is_inferred_from_indexed_iterator() {
+        let mut mon = AtomicProgressMonitor::new("items", 0, |_, _| {});
+        let sum: u64 = (0..10_000u64).into_par_iter().with_progress(&mon).sum();
+        assert_eq!(sum, 49_995_000);
+        assert_eq!(mon.total().as_ref(), &NumericWork::new(10_000u64));
+        assert_eq!(mon.completed().as_ref(), &NumericWork::new(10_000u64));
+        mon.close().unwrap();
+
+        let mut mon = SharedProgressMonitor::indeterminate(
+            "items",
+            |_: &NumericWork<u64>, _: &NumericWork<u64>| {},
+        );
+        let mut subscription = mon.subscribe();
+        (0..100u64)
+            .into_par_iter()
+            .with_progress(&mon)
+            .for_each(|_| {});
+        assert!(!mon.is_indeterminate());
+        assert_eq!(mon.to_string(), "100/100");
+        assert_eq!(subscription.latest().total, NumericWork::new(100u64));
+        mon.close().unwrap();
+    }
+}
@@ -0,0 +1,194 @@
+//! Track the progress of a spawned process, which reports its own progress on stdout or stderr.
+//!
+//! By default, lines of the form `PROGRESS <done>/<total>` are understood, e.g. `PROGRESS 40/100`.
+//! With the `json` feature, JSON lines of the form `{"done": 40, "total": 100}` are understood as well.
+//! The progress reported is mapped onto the scale of the monitor given, so that the process can be tracked by any `ChildMonitor`.
+//! All other lines are passed through to the stdout or stderr of the current process.
+
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    process::{Command, ExitStatus, Stdio},
+};
+
+use crate::{
+    monitor::ProgressMonitor,
+    work::numeric::{NumReq, NumericWork},
+};
+
+/// The output stream of the spawned process on which it reports its progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressSource {
+    Stdout,
+    Stderr,
+}
+
+/// Progress reported by a spawned process, on its own scale.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProgressLine {
+    pub done: f64,
+    pub total: f64,
+}
+
+impl ProgressLine {
+    /// Progress with `done` and `total` being finite numbers. `NaN` and infinite values are rejected.
+    pub fn new(done: f64, total: f64) -> Option<Self> {
+        (done.is_finite() && total.is_finite()).then_some(Self { done, total })
+    }
+
+    /// Parse a line of the form `PROGRESS <done>/<total>`.
+    pub fn parse_text(line: &str) -> Option<Self> {
+        let (done, total) = line.trim().strip_prefix("PROGRESS ")?.split_once('/')?;
+        Self::new(done.trim().parse().ok()?, total.trim().parse().ok()?)
+    }
+
+    /// Parse a JSON line of the form `{"done": <done>, "total": <total>}`. Requires the `json` feature.
+    #[cfg(feature = "json")]
+    pub fn parse_json(line: &str) -> Option<Self> {
+        let value: serde_json::Value = serde_json::from_str(line.trim()).ok()?;
+        Self::new(value.get("done")?.as_f64()?, value.get("total")?.as_f64()?)
+    }
+
+    /// Parse any of the supported formats.
+    pub fn parse(line: &str) -> Option<Self> {
+        #[cfg(feature = "json")]
+        if let Some(progress) = Self::parse_json(line) {
+            return Some(progress);
+        }
+        Self::parse_text(line)
+    }
+}
+
+/// Spawns a command and forwards the progress it reports into a monitor.
+///
+/// The monitor is not closed. Inspect the exit status returned by `run` and close the monitor accordingly.
+pub struct ProgressCommand<'c, P: FnMut(&str) -> Option<ProgressLine>> {
+    command: &'c mut Command,
+    source: ProgressSource,
+    parser: P,
+}
+
+impl<'c> ProgressCommand<'c, fn(&str) -> Option<ProgressLine>> {
+    /// Reads progress from stdout, using `ProgressLine::parse`.
+    pub fn new(command: &'c mut Command) -> Self {
+        Self {
+            command,
+            source: ProgressSource::Stdout,
+            parser: ProgressLine::parse,
+        }
+    }
+}
+
+impl<'c, P: FnMut(&str) -> Option<ProgressLine>> ProgressCommand<'c, P> {
+    pub fn source(mut self, source: ProgressSource) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// Use a custom parser. Lines for which the parser returns `None` are passed through.
+    pub fn parser<P2: FnMut(&str) -> Option<ProgressLine>>(
+        self,
+        parser: P2,
+    ) -> ProgressCommand<'c, P2> {
+        ProgressCommand {
+            command: self.command,
+            source: self.source,
+            parser,
+        }
+    }
+
+    /// Run the command to completion, forwarding all progress it reports into `monitor`.
+    pub fn run<N, M>(mut self, monitor: &mut M) -> io::Result<ExitStatus>
+    where
+        N: NumReq,
+        M: ProgressMonitor<NumericWork<N>>,
+    {
+        match self.source {
+            ProgressSource::Stdout => {
+                let mut child = self.command.stdout(Stdio::piped()).spawn()?;
+                let stdout = child.stdout.take().expect("piped stdout");
+                self.forward(stdout, io::stdout(), monitor)?;
+                child.wait()
+            }
+            ProgressSource::Stderr => {
+                let mut child = self.command.stderr(Stdio::piped()).spawn()?;
+                let stderr = child.stderr.take().expect("piped stderr");
+                self.forward(stderr, io::stderr(), monitor)?;
+                child.wait()
+            }
+        }
+    }
+
+    fn forward<N, M, R, O>(
+        &mut self,
+        output: R,
+        mut passthrough: O,
+        monitor: &mut M,
+    ) -> io::Result<()>
+    where
+        N: NumReq,
+        M: ProgressMonitor<NumericWork<N>>,
+        R: Read,
+        O: Write,
+    {
+        for line in BufReader::new(output).lines() {
+            let line = line?;
+            match (self.parser)(&line) {
+                Some(progress) => advance(monitor, progress),
+                None => writeln!(passthrough, "{line}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Advance `monitor` to the relative progress reported. Progress never moves backwards.
+/// Progress which cannot be mapped onto the monitor is skipped. Whatever the process prints must never crash us.
+fn advance<N, M>(monitor: &mut M, progress: ProgressLine)
+where
+    N: NumReq,
+    M: ProgressMonitor<NumericWork<N>>,
+{
+    let relative = progress.done / progress.total;
+    if !relative.is_finite() || progress.total <= 0.0 {
+        return;
+    }
+    let target = monitor
+        .total()
+        .value()
+        .to_f64()
+        .and_then(|total| N::from_f64((relative.clamp(0.0, 1.0) * total).floor()));
+    let target = match target {
+        Some(target) => target,
+        None => return,
+    };
+    let completed = monitor.completed().value().clone();
+    if target > completed {
+        monitor.worked(target - completed);
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use std::process::Command;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn progress_lines_advance_the_monitor() {
+        let mut root = CallbackProgressMonitor::silent("root", 100);
+        {
+            let mut child = root.new_child("tool", 100u64, 1000u64);
+            let mut command = Command::new("sh");
+            command.args([
+                "-c",
+                "echo 'PROGRESS 1/4'; echo working; echo 'PROGRESS nan/1'; echo 'PROGRESS 1/0'; echo 'PROGRESS 4/4'",
+            ]);
+            let status = ProgressCommand::new(&mut command).run(&mut child).unwrap();
+            assert!(status.success());
+            assert_eq!(ProgressLine::parse_text("PROGRESS inf/1"), None);
+            assert_eq!(child.completed().as_ref(), &NumericWork::new(1000u64));
+            child.close().unwrap();
+        }
+        root.close().unwrap();
+    }
+}
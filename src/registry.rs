@@ -0,0 +1,258 @@
+//! A global registry of all monitors currently alive. Requires the `registry` feature.
+//!
+//! Every monitor registers itself when created and deregisters when dropped.
+//! Use `snapshot` to list what is currently in progress, e.g. from a debug endpoint,
+//! or `install_panic_hook` to print it whenever a thread panics.
+//!
+//! Registered monitors update their entry whenever work is submitted, rendering their work as strings.
+//! Only the entry of the monitor itself is locked for that, not the registry as a whole.
+//! The `AtomicProgressMonitor` does not register itself, as that would defeat its purpose.
+
+use std::{
+    fmt::{Display, Write},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError, TryLockError,
+    },
+};
+
+/// Identifies a monitor in the registry. Ids increase in order of creation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MonitorId(u64);
+
+/// The registry entry of a monitor. Work is kept in its rendered (`Display`) form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisteredMonitor {
+    pub id: MonitorId,
+    pub name: String,
+    pub parent: Option<MonitorId>,
+    pub total: String,
+    pub completed: String,
+    pub closed: bool,
+    pub paused: bool,
+}
+
+type Entry = Arc<Mutex<RegisteredMonitor>>;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+/// Sorted by id, as monitors are appended when created.
+static REGISTRY: Mutex<Vec<(MonitorId, Entry)>> = Mutex::new(Vec::new());
+
+fn lock() -> MutexGuard<'static, Vec<(MonitorId, Entry)>> {
+    REGISTRY.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn lock_entry(entry: &Entry) -> MutexGuard<'_, RegisteredMonitor> {
+    entry.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// The entry of a monitor in the registry, deregistering it when dropped.
+///
+/// Updates only lock the entry of the monitor itself. The global lock is taken when registering and deregistering only.
+#[derive(Debug)]
+pub(crate) struct Registration {
+    id: Option<MonitorId>,
+    /// `None` if the monitor stands in for another one. See `alias`.
+    entry: Option<Entry>,
+}
+
+impl Registration {
+    /// Stands in for the monitor `id` without an entry of its own, e.g. for intermediate monitors.
+    /// Children of the monitor are listed as children of `id`.
+    pub(crate) fn alias(id: Option<MonitorId>) -> Self {
+        Self { id, entry: None }
+    }
+
+    pub(crate) fn id(&self) -> Option<MonitorId> {
+        self.id
+    }
+
+    pub(crate) fn set_parent(&self, parent: Option<MonitorId>) {
+        if let Some(entry) = &self.entry {
+            lock_entry(entry).parent = parent;
+        }
+    }
+
+    pub(crate) fn update(
+        &self,
+        total: &dyn Display,
+        completed: &dyn Display,
+        closed: bool,
+        paused: bool,
+    ) {
+        let entry = match &self.entry {
+            Some(entry) => entry,
+            None => return,
+        };
+        let mut entry = lock_entry(entry);
+        entry.total = total.to_string();
+        entry.completed = completed.to_string();
+        entry.closed = closed;
+        entry.paused = paused;
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        if self.entry.is_none() {
+            return;
+        }
+        let mut registry = lock();
+        if let Ok(index) = registry.binary_search_by_key(&self.id, |(id, _)| Some(*id)) {
+            registry.remove(index);
+        }
+    }
+}
+
+pub(crate) fn register(name: &str, total: &dyn Display, completed: &dyn Display) -> Registration {
+    let mut registry = lock();
+    // Taken while holding the lock, keeping the registry sorted by id.
+    let id = MonitorId(NEXT_ID.fetch_add(1, Ordering::Relaxed));
+    let entry = Arc::new(Mutex::new(RegisteredMonitor {
+        id,
+        name: name.to_owned(),
+        parent: None,
+        total: total.to_string(),
+        completed: completed.to_string(),
+        closed: false,
+        paused: false,
+    }));
+    registry.push((id, Arc::clone(&entry)));
+    Registration {
+        id: Some(id),
+        entry: Some(entry),
+    }
+}
+
+/// List all monitors currently alive, in order of their creation.
+pub fn snapshot() -> Vec<RegisteredMonitor> {
+    lock()
+        .iter()
+        .map(|(_, entry)| lock_entry(entry).clone())
+        .collect()
+}
+
+/// Like `snapshot`, but never waits for a lock. Entries currently locked are left out.
+/// Returns `None` if the registry itself is locked.
+fn try_snapshot() -> Option<Vec<RegisteredMonitor>> {
+    let registry = match REGISTRY.try_lock() {
+        Ok(registry) => registry,
+        Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+        Err(TryLockError::WouldBlock) => return None,
+    };
+    Some(
+        registry
+            .iter()
+            .filter_map(|(_, entry)| match entry.try_lock() {
+                Ok(entry) => Some(entry.clone()),
+                Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner().clone()),
+                Err(TryLockError::WouldBlock) => None,
+            })
+            .collect(),
+    )
+}
+
+/// Render all monitors currently alive as a tree, children indented below their parents.
+pub fn render() -> String {
+    render_entries(&snapshot())
+}
+
+fn render_entries(entries: &[RegisteredMonitor]) -> String {
+    fn render_entry(
+        out: &mut String,
+        entries: &[RegisteredMonitor],
+        entry: &RegisteredMonitor,
+        depth: usize,
+    ) {
+        let _ = writeln!(
+            out,
+            "{:indent$}{}: {}/{}{}",
+            "",
+            entry.name,
+            entry.completed,
+            entry.total,
+            match (entry.closed, entry.paused) {
+                (true, _) => " (closed)",
+                (false, true) => " (paused)",
+                (false, false) => "",
+            },
+            indent = depth * 2
+        );
+        for child in entries.iter().filter(|it| it.parent == Some(entry.id)) {
+            render_entry(out, entries, child, depth + 1);
+        }
+    }
+
+    let mut out = String::new();
+    // Monitors whose parent is not registered are rendered as roots.
+    for entry in entries.iter().filter(|entry| match entry.parent {
+        None => true,
+        Some(parent) => !entries.iter().any(|it| it.id == parent),
+    }) {
+        render_entry(&mut out, entries, entry, 0);
+    }
+    out
+}
+
+/// Print all monitors alive to stderr whenever a thread panics. Calls the previously installed hook afterwards.
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // We must not wait for any lock, as the panicking thread might hold it.
+        if let Some(entries) = try_snapshot() {
+            eprintln!("Monitors in progress:\n{}", render_entries(&entries));
+        }
+        previous(info);
+    }));
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+
+    #[test]
+    fn monitors_are_registered_while_alive() {
+        let mut root = CallbackProgressMonitor::silent("registry-root", 10);
+        let root_id = root.registry_id().unwrap();
+        {
+            let mut child = root.new_child("registry-child", 10u64, 2u64);
+            child.worked(1u64);
+
+            let entries = crate::registry::snapshot();
+            let child_entry = entries
+                .iter()
+                .find(|it| it.name == "registry-child")
+                .unwrap();
+            assert_eq!(child_entry.parent, Some(root_id));
+            assert_eq!(child_entry.completed, "1");
+            assert!(crate::registry::render().contains("  registry-child: 1/2"));
+
+            child.worked(1u64);
+            child.close().unwrap();
+        }
+        assert!(!crate::registry::snapshot()
+            .iter()
+            .any(|it| it.name == "registry-child"));
+        root.close().unwrap();
+    }
+
+    #[test]
+    fn split_parts_are_registered_below_the_split_monitor() {
+        let mut root = CallbackProgressMonitor::silent("registry-split-root", 10);
+        let root_id = root.registry_id().unwrap();
+        let parts = root.split([("registry-part", 10u64, 1u64)]);
+
+        let children: Vec<String> = crate::registry::snapshot()
+            .into_iter()
+            .filter(|it| it.parent == Some(root_id))
+            .map(|it| it.name)
+            .collect();
+        assert_eq!(children, vec!["registry-part"]);
+
+        for mut part in parts {
+            part.worked(1u64);
+            part.close().unwrap();
+        }
+        root.close().unwrap();
+    }
+}
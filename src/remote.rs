@@ -0,0 +1,505 @@
+//! Cross-process progress reporting over a Unix domain socket. Requires the `remote` feature.
+//!
+//! A `ProgressServer` listens on a socket and exposes each connected client as a child of a local `SharedProgressMonitor`.
+//! Clients report their progress through a `RemoteProgressMonitor`.
+//!
+//! The protocol is line based. A client first announces itself with `CHILD <parent_work> <child_work> <name>`.
+//! The server answers with `OK`, or with `REJECTED <reason>` if it cannot track the child, e.g. as its root has not enough work left.
+//! Once accepted, the client sends any number of `WORKED <amount>`, `UNWORKED <amount>`, `TOTAL <amount>`, `PAUSE` and `RESUME` lines
+//! and a final `CLOSE`, `CANCEL` if the client was cancelled or `SKIP <reason>` if its work became unnecessary.
+//! Clients disconnecting without closing their monitor are reported just like dropping an unclosed child.
+
+use std::{
+    borrow::Cow,
+    fmt::{Debug, Display},
+    io::{self, BufRead, BufReader, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    str::FromStr,
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{
+    error::WorkError,
+    monitor::{
+        cancel::CancellationToken, clock::Clock, config::MonitorConfig,
+        shared::SharedProgressMonitor, state::MonitorState, CloseOutcome, ProgressMonitor,
+    },
+    work::{
+        bytes::{ByteUnits, ByteWork},
+        duration::DurationWork,
+        float::FloatWork,
+        numeric::{NumReq, NumericWork},
+        Work,
+    },
+    CloseError,
+};
+
+/// Work which can be transferred between processes.
+pub trait WireWork: Work {
+    /// Encode this work as a single line of text. Must not contain line breaks.
+    fn encode(&self) -> String;
+
+    fn decode(encoded: &str) -> Option<Self>;
+}
+
+impl<N: NumReq + FromStr> WireWork for NumericWork<N> {
+    fn encode(&self) -> String {
+        self.to_string()
+    }
+
+    fn decode(encoded: &str) -> Option<Self> {
+        encoded.parse::<N>().ok().map(Self::new)
+    }
+}
+
+impl<U: ByteUnits> WireWork for ByteWork<U> {
+    fn encode(&self) -> String {
+        self.bytes().to_string()
+    }
+
+    fn decode(encoded: &str) -> Option<Self> {
+        encoded.parse::<u64>().ok().map(Self::new)
+    }
+}
+
+impl WireWork for DurationWork {
+    fn encode(&self) -> String {
+        self.duration().as_nanos().to_string()
+    }
+
+    fn decode(encoded: &str) -> Option<Self> {
+        encoded
+            .parse::<u64>()
+            .ok()
+            .map(|nanos| Self::new(Duration::from_nanos(nanos)))
+    }
+}
+
+impl WireWork for FloatWork {
+    fn encode(&self) -> String {
+        self.to_string()
+    }
+
+    fn decode(encoded: &str) -> Option<Self> {
+        encoded.parse::<f64>().ok().map(Self::new)
+    }
+}
+
+/// Listens on a Unix domain socket for `RemoteProgressMonitor`s, tracking each one as a child of `root`.
+/// The socket file is removed when the server is dropped.
+#[derive(Debug)]
+pub struct ProgressServer<W: WireWork> {
+    path: PathBuf,
+    listener: UnixListener,
+    root: SharedProgressMonitor<'static, W>,
+}
+
+impl<W: WireWork + Send + 'static> ProgressServer<W> {
+    pub fn bind<P: AsRef<Path>>(
+        path: P,
+        root: SharedProgressMonitor<'static, W>,
+    ) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let listener = UnixListener::bind(&path)?;
+        Ok(Self {
+            path,
+            listener,
+            root,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Wait for the next client and track its progress on a new thread.
+    /// The returned handle finishes once the client disconnected.
+    pub fn accept(&self) -> io::Result<JoinHandle<()>> {
+        let (stream, _addr) = self.listener.accept()?;
+        let root = self.root.clone();
+        Ok(thread::spawn(move || {
+            if let Err(err) = serve_client(stream, &root) {
+                tracing::error!("Failed to serve remote progress monitor. Reason: {err}");
+            }
+        }))
+    }
+
+    /// Accept clients on a background thread, until accepting a client fails.
+    pub fn spawn(self) -> JoinHandle<()> {
+        thread::spawn(move || loop {
+            if let Err(err) = self.accept() {
+                tracing::error!("Stopped accepting remote progress monitors. Reason: {err}");
+                break;
+            }
+        })
+    }
+}
+
+impl<W: WireWork> Drop for ProgressServer<W> {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn invalid(line: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Unexpected message: '{line}'"),
+    )
+}
+
+/// Creates the child announced by the first line a client sent.
+fn handshake<W: WireWork>(
+    hello: &str,
+    root: &SharedProgressMonitor<'static, W>,
+) -> io::Result<SharedProgressMonitor<'static, W>> {
+    let mut parts = hello.splitn(4, ' ');
+    let (parent_work, child_work, name) =
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some("CHILD"), Some(parent_work), Some(child_work), Some(name)) => (
+                W::decode(parent_work).ok_or_else(|| invalid(hello))?,
+                W::decode(child_work).ok_or_else(|| invalid(hello))?,
+                name.to_owned(),
+            ),
+            _ => return Err(invalid(hello)),
+        };
+    root.try_new_child(name, parent_work, child_work)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+fn serve_client<W: WireWork>(
+    stream: UnixStream,
+    root: &SharedProgressMonitor<'static, W>,
+) -> io::Result<()> {
+    let mut replies = stream.try_clone()?;
+    let mut lines = BufReader::new(stream).lines();
+
+    let hello = match lines.next() {
+        Some(line) => line?,
+        None => return Ok(()),
+    };
+    // Dropping the child when the client disconnects reports a missing or failed close.
+    let mut child = match handshake(&hello, root) {
+        Ok(child) => {
+            writeln!(replies, "OK")?;
+            child
+        }
+        Err(err) => {
+            // The client learns about it while connecting, instead of failing to report its progress later on.
+            writeln!(replies, "REJECTED {}", err.to_string().replace('\n', " "))?;
+            return Err(err);
+        }
+    };
+    for line in lines {
+        let line = line?;
+        match line.split_once(' ') {
+            Some(("WORKED", amount)) => {
+                child.worked(W::decode(amount).ok_or_else(|| invalid(&line))?)
+            }
+            Some(("UNWORKED", amount)) => {
+                child.unworked(W::decode(amount).ok_or_else(|| invalid(&line))?)
+            }
+            Some(("SKIP", reason)) => {
+                child.skip(reason);
+            }
+            Some(("TOTAL", total)) => {
+                child.set_total(W::decode(total).ok_or_else(|| invalid(&line))?)
+            }
+            _ if line == "PAUSE" => child.pause(),
+            _ if line == "RESUME" => child.resume(),
+            _ if line == "CANCEL" => {
+                child.cancel();
+                let _ = child.close();
+            }
+            _ if line == "CLOSE" => {
+                if let Err(err) = child.close() {
+                    tracing::error!(
+                        "Remote progress monitor could not be closed. Reason: {}",
+                        err
+                    );
+                }
+            }
+            _ => return Err(invalid(&line)),
+        }
+    }
+    Ok(())
+}
+
+/// A monitor reporting its progress to a `ProgressServer` in some other process.
+/// It tracks its progress locally as well, so that the usual close and overshoot rules apply.
+///
+/// Failing to reach the server never interrupts the work being done. Errors are only logged.
+#[derive(Debug)]
+pub struct RemoteProgressMonitor<W: WireWork> {
+    state: MonitorState<'static, W>,
+    stream: UnixStream,
+}
+
+impl<W: WireWork> RemoteProgressMonitor<W> {
+    /// Connect to the server listening on `path`, announcing a child responsible for `parent_work` of the servers root,
+    /// using its own scale of `child_work`.
+    /// Fails with `io::ErrorKind::ConnectionRefused` if the server rejects the child.
+    pub fn connect<P, N, A1, A2>(
+        path: P,
+        name: N,
+        parent_work: A1,
+        child_work: A2,
+    ) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+        N: Into<Cow<'static, str>>,
+        A1: Into<W>,
+        A2: Into<W>,
+    {
+        let name = name.into();
+        let parent_work: W = parent_work.into();
+        let child_work: W = child_work.into();
+
+        let mut stream = UnixStream::connect(path)?;
+        writeln!(
+            stream,
+            "CHILD {} {} {}",
+            parent_work.encode(),
+            child_work.encode(),
+            name.replace('\n', " ")
+        )?;
+        let mut reply = String::new();
+        BufReader::new(&stream).read_line(&mut reply)?;
+        match reply.trim_end() {
+            "OK" => {}
+            "" => return Err(io::ErrorKind::UnexpectedEof.into()),
+            reply => {
+                let reason = reply.strip_prefix("REJECTED ").unwrap_or(reply);
+                return Err(io::Error::new(
+                    io::ErrorKind::ConnectionRefused,
+                    reason.to_owned(),
+                ));
+            }
+        }
+
+        Ok(Self {
+            state: MonitorState::new(name, child_work),
+            stream,
+        })
+    }
+
+    pub fn name(&self) -> Cow<'static, str> {
+        self.state.name.clone()
+    }
+
+    pub fn with_config(mut self, config: MonitorConfig) -> Self {
+        self.state.config = config;
+        self
+    }
+
+    fn send(&mut self, message: std::fmt::Arguments<'_>) {
+        if let Err(err) = self.stream.write_fmt(message) {
+            tracing::warn!(name = %self.state.name, "Could not report progress to server. Reason: {err}");
+        }
+    }
+}
+
+impl<W: WireWork> ProgressMonitor<W> for RemoteProgressMonitor<W> {
+    fn name(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.state.name)
+    }
+
+    fn try_worked<A: Into<W>>(&mut self, amount_of_work: A) -> Result<(), WorkError> {
+        let accepted = self.state.try_worked(amount_of_work.into())?;
+        self.send(format_args!("WORKED {}\n", accepted.encode()));
+        Ok(())
+    }
+
+    fn try_unworked<A: Into<W>>(&mut self, amount_of_work: A) -> Result<(), WorkError> {
+        let taken = self.state.try_unworked(amount_of_work.into())?;
+        self.send(format_args!("UNWORKED {}\n", taken.encode()));
+        Ok(())
+    }
+
+    fn total(&self) -> Cow<'_, W> {
+        Cow::Borrowed(&self.state.work)
+    }
+
+    fn try_set_total<A: Into<W>>(&mut self, total: A) -> Result<(), WorkError> {
+        self.state.try_set_total(total.into())?;
+        self.send(format_args!("TOTAL {}\n", self.state.work.encode()));
+        Ok(())
+    }
+
+    fn completed(&self) -> Cow<'_, W> {
+        Cow::Borrowed(&self.state.work_done)
+    }
+
+    fn remaining(&self) -> Cow<'_, W> {
+        Cow::Owned(self.state.remaining())
+    }
+
+    fn close(&mut self) -> Result<CloseOutcome, CloseError> {
+        let result = self.state.close();
+        match result {
+            Ok(CloseOutcome::Cancelled) => self.send(format_args!("CANCEL\n")),
+            _ => self.send(format_args!("CLOSE\n")),
+        }
+        result
+    }
+
+    fn pause(&mut self) {
+        if !self.state.clock.is_paused() {
+            self.state.pause();
+            self.send(format_args!("PAUSE\n"));
+        }
+    }
+
+    fn resume(&mut self) {
+        if self.state.clock.is_paused() {
+            self.state.resume();
+            self.send(format_args!("RESUME\n"));
+        }
+    }
+
+    fn clock(&self) -> Clock {
+        self.state.clock
+    }
+
+    fn skip<R: Into<String>>(&mut self, reason: R) -> CloseOutcome {
+        if self.state.is_closed() {
+            return self.state.skip(reason.into());
+        }
+        let reason = reason.into().replace('\n', " ");
+        self.send(format_args!("SKIP {reason}\n"));
+        self.state.skip(reason)
+    }
+
+    fn cancellation_token(&self) -> CancellationToken {
+        self.state.token.clone()
+    }
+
+    fn config(&self) -> MonitorConfig {
+        self.state.config
+    }
+
+    fn path(&self) -> Vec<String> {
+        self.state.path.clone()
+    }
+
+    #[cfg(feature = "registry")]
+    fn registry_id(&self) -> Option<crate::registry::MonitorId> {
+        self.state.registration.id()
+    }
+}
+
+impl<W: WireWork> Display for RemoteProgressMonitor<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}/{}", self.state.work_done, self.state.work))
+    }
+}
+
+impl<W: WireWork> Drop for RemoteProgressMonitor<W> {
+    fn drop(&mut self) {
+        // The server reports a missing close on its own, as we disconnect without sending one.
+        match &self.state.closed {
+            Some(Ok(_)) => { /* do nothing */ }
+            Some(Err(err)) => {
+                tracing::error!(
+                    "RemoteProgressMonitor was not successfully closed. Reason: {}",
+                    err
+                );
+            }
+            None => tracing::warn!("close() was not called on {self:?}!"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{io, path::PathBuf, thread};
+
+    use crate::prelude::*;
+
+    fn socket(test: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "progress-monitor-{}-{test}.sock",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn remote_child_reports_into_root() {
+        let path = socket("report");
+
+        let mut root = SharedProgressMonitor::new(
+            "root",
+            100u64,
+            |_: &NumericWork<u64>, _: &NumericWork<u64>| {},
+        );
+        let server = ProgressServer::bind(&path, root.clone()).unwrap();
+
+        let client = thread::spawn({
+            let path = path.clone();
+            move || {
+                let mut remote = RemoteProgressMonitor::<NumericWork<u64>>::connect(
+                    path,
+                    "remote step",
+                    100u64,
+                    10u64,
+                )
+                .unwrap();
+                remote.worked(4u64);
+                remote.worked(6u64);
+                remote.close().unwrap();
+            }
+        });
+        server.accept().unwrap().join().unwrap();
+        client.join().unwrap();
+
+        assert_eq!(root.completed().as_ref(), &NumericWork::new(100u64));
+        root.close().unwrap();
+    }
+
+    #[test]
+    fn disconnected_and_rejected_clients() {
+        let path = socket("disconnect");
+        let mut root = SharedProgressMonitor::new(
+            "root",
+            100u64,
+            |_: &NumericWork<u64>, _: &NumericWork<u64>| {},
+        );
+        let server = ProgressServer::bind(&path, root.clone()).unwrap();
+
+        // The client goes away without closing its monitor, e.g. because its process crashed.
+        let client = thread::spawn({
+            let path = path.clone();
+            move || {
+                let mut remote = RemoteProgressMonitor::<NumericWork<u64>>::connect(
+                    path, "crashing", 50u64, 10u64,
+                )
+                .unwrap();
+                remote.worked(4u64);
+                drop(remote);
+            }
+        });
+        server.accept().unwrap().join().unwrap();
+        client.join().unwrap();
+        assert_eq!(root.completed().as_ref(), &NumericWork::new(20u64));
+
+        let client = thread::spawn({
+            let path = path.clone();
+            move || {
+                RemoteProgressMonitor::<NumericWork<u64>>::connect(path, "excess", 90u64, 10u64)
+                    .unwrap_err()
+            }
+        });
+        server.accept().unwrap().join().unwrap();
+        assert_eq!(
+            client.join().unwrap().kind(),
+            io::ErrorKind::ConnectionRefused
+        );
+
+        root.worked(80u64);
+        root.close().unwrap();
+    }
+}
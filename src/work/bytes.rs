@@ -0,0 +1,152 @@
+use std::{
+    fmt::{Debug, Display},
+    marker::PhantomData,
+    ops::{Add, Sub},
+};
+
+use crate::work::{numeric::NumericWork, AddError, Work};
+
+/// The units an amount of bytes is displayed in. See `ByteWork`.
+pub trait ByteUnits: Debug + Clone + Copy + PartialEq + Eq + PartialOrd + Ord {
+    /// The factor between two consecutive units.
+    const BASE: u64;
+    /// The names of all units, starting with bytes.
+    const UNITS: [&'static str; 7];
+}
+
+/// Units based on powers of 1024: KiB, MiB, GiB, ...
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Binary;
+
+impl ByteUnits for Binary {
+    const BASE: u64 = 1024;
+    const UNITS: [&'static str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+}
+
+/// Units based on powers of 1000: kB, MB, GB, ...
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Si;
+
+impl ByteUnits for Si {
+    const BASE: u64 = 1000;
+    const UNITS: [&'static str; 7] = ["B", "kB", "MB", "GB", "TB", "PB", "EB"];
+}
+
+/// An amount of bytes, e.g. downloaded or written. Displayed in human-readable units, binary ones by default.
+///
+/// Amounts are displayed with one decimal place, unless a precision is given, e.g. `{:.2}`. Whole bytes have none.
+/// Use `ByteWork<Si>` to display amounts in SI units instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ByteWork<U: ByteUnits = Binary>(u64, PhantomData<U>);
+
+impl<U: ByteUnits> ByteWork<U> {
+    pub fn bytes(&self) -> u64 {
+        self.0
+    }
+}
+
+impl<U: ByteUnits> Work for ByteWork<U> {
+    type Type = u64;
+
+    fn new<A: Into<Self::Type>>(value: A) -> Self {
+        Self(value.into(), PhantomData)
+    }
+
+    fn zero() -> Self {
+        Self::new(0u64)
+    }
+
+    fn min<'a>(a: &'a Self, b: &'a Self) -> &'a Self {
+        if a.0 < b.0 {
+            a
+        } else {
+            b
+        }
+    }
+
+    fn parent_work_done_when(
+        sub_work_done: Self,
+        of_total_sub_work: Self,
+        of_parent_work: Self,
+    ) -> Self {
+        let scaled = NumericWork::<u64>::parent_work_done_when(
+            NumericWork::new(sub_work_done.0),
+            NumericWork::new(of_total_sub_work.0),
+            NumericWork::new(of_parent_work.0),
+        );
+        Self::new(*scaled.value())
+    }
+}
+
+impl<U: ByteUnits> Display for ByteWork<U> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut unit = 0;
+        let mut scale = 1u64;
+        while unit + 1 < U::UNITS.len() && self.0 / scale >= U::BASE {
+            unit += 1;
+            scale *= U::BASE;
+        }
+        if unit == 0 {
+            return write!(f, "{} {}", self.0, U::UNITS[0]);
+        }
+        let precision = f.precision().unwrap_or(1);
+        write!(
+            f,
+            "{:.precision$} {}",
+            self.0 as f64 / scale as f64,
+            U::UNITS[unit]
+        )
+    }
+}
+
+impl<U: ByteUnits> Add for ByteWork<U> {
+    type Output = Result<Self, AddError>;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.0
+            .checked_add(rhs.0)
+            .map(Self::new)
+            .ok_or(AddError::Overflow)
+    }
+}
+
+impl<U: ByteUnits> Sub for ByteWork<U> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.0 - rhs.0)
+    }
+}
+
+impl<U: ByteUnits> From<u64> for ByteWork<U> {
+    fn from(value: u64) -> Self {
+        Self::new(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::work::{
+        bytes::{Binary, ByteWork, Si},
+        Work,
+    };
+
+    #[test]
+    fn bytes_are_displayed_in_units() {
+        assert_eq!(ByteWork::<Si>::new(512u64).to_string(), "512 B");
+        assert_eq!(ByteWork::<Si>::new(1_500_000u64).to_string(), "1.5 MB");
+        assert_eq!(
+            format!(
+                "{}/{}",
+                ByteWork::<Binary>::new(734_003_200u64),
+                ByteWork::<Binary>::new(1_073_741_824u64)
+            ),
+            "700.0 MiB/1.0 GiB"
+        );
+        assert_eq!(
+            format!("{:.2}", ByteWork::<Binary>::new(1_536u64)),
+            "1.50 KiB"
+        );
+        assert_eq!(ByteWork::<Binary>::new(u64::MAX).to_string(), "16.0 EiB");
+    }
+}
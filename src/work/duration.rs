@@ -0,0 +1,160 @@
+use std::{
+    fmt::Display,
+    ops::{Add, Sub},
+    time::Duration,
+};
+
+use crate::work::{AddError, Work};
+
+/// Work measured in time, e.g. a warmup phase of 30 seconds or polling for up to 5 minutes.
+///
+/// Like any other work, it is submitted explicitly. Nothing advances on its own as time passes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DurationWork(Duration);
+
+impl DurationWork {
+    pub fn duration(&self) -> Duration {
+        self.0
+    }
+}
+
+impl Work for DurationWork {
+    type Type = Duration;
+
+    fn new<A: Into<Self::Type>>(value: A) -> Self {
+        Self(value.into())
+    }
+
+    fn zero() -> Self {
+        Self(Duration::ZERO)
+    }
+
+    fn min<'a>(a: &'a Self, b: &'a Self) -> &'a Self {
+        if a.0 < b.0 {
+            a
+        } else {
+            b
+        }
+    }
+
+    /// Scaled exactly in nanoseconds, rounding down, unless that is not representable.
+    fn parent_work_done_when(
+        sub_work_done: Self,
+        of_total_sub_work: Self,
+        of_parent_work: Self,
+    ) -> Self {
+        let total = of_total_sub_work.0.as_nanos();
+        if total == 0 {
+            return Self::zero();
+        }
+        let exact = sub_work_done
+            .0
+            .as_nanos()
+            .checked_mul(of_parent_work.0.as_nanos())
+            .and_then(|product| u64::try_from(product / total).ok());
+        match exact {
+            Some(nanos) => Self(Duration::from_nanos(nanos)),
+            None => Self(
+                of_parent_work
+                    .0
+                    .mul_f64(sub_work_done.0.as_secs_f64() / of_total_sub_work.0.as_secs_f64()),
+            ),
+        }
+    }
+}
+
+impl Display for DurationWork {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let secs = self.0.as_secs();
+        if secs >= 3600 {
+            write!(
+                f,
+                "{}h {:02}m {:02}s",
+                secs / 3600,
+                secs % 3600 / 60,
+                secs % 60
+            )
+        } else if secs >= 60 {
+            write!(f, "{}m {:02}s", secs / 60, secs % 60)
+        } else if secs >= 1 {
+            write!(f, "{:.1}s", self.0.as_secs_f64())
+        } else {
+            write!(f, "{}ms", self.0.as_millis())
+        }
+    }
+}
+
+impl Add for DurationWork {
+    type Output = Result<Self, AddError>;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.0
+            .checked_add(rhs.0)
+            .map(Self)
+            .ok_or(AddError::Overflow)
+    }
+}
+
+impl Sub for DurationWork {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self(self.0 - rhs.0)
+    }
+}
+
+impl From<Duration> for DurationWork {
+    fn from(value: Duration) -> Self {
+        Self::new(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn time_budgeted_phases() {
+        let mut root = CallbackProgressMonitor::new(
+            "startup",
+            Duration::from_secs(300),
+            |_: &DurationWork, _: &DurationWork| {},
+        );
+        assert_eq!(root.total().to_string(), "5m 00s");
+        {
+            // A warmup of 30s, taking up a tenth of the startup.
+            let mut warmup =
+                root.new_child("warmup", Duration::from_secs(30), Duration::from_secs(30));
+            warmup.worked(Duration::from_millis(1500));
+            assert_eq!(warmup.completed().to_string(), "1.5s");
+            warmup.finish().unwrap();
+        }
+        assert_eq!(
+            root.completed().as_ref(),
+            &DurationWork::new(Duration::from_secs(30))
+        );
+        root.finish().unwrap();
+    }
+
+    #[test]
+    fn time_scaled_children_of_numeric_monitors() {
+        let mut root = CallbackProgressMonitor::silent("migration", 100);
+        {
+            let mut poll = root.new_child(
+                "poll",
+                50u64,
+                NumericWork::from_nanos(Duration::from_secs(300)),
+            );
+            poll.worked(NumericWork::from_nanos(Duration::from_secs(60)));
+            assert_eq!(
+                poll.remaining().as_ref(),
+                &NumericWork::from_nanos(Duration::from_secs(240))
+            );
+            poll.finish().unwrap();
+        }
+        assert_eq!(root.completed().as_ref(), &NumericWork::new(50u64));
+        root.finish().unwrap();
+    }
+}
@@ -0,0 +1,160 @@
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    ops::{Add, Sub},
+};
+
+use crate::work::{AddError, Work};
+
+/// Work measured in fractional amounts, e.g. gigabytes processed.
+///
+/// Floating point arithmetic accumulates rounding errors, so amounts are compared with a tolerance:
+/// Two amounts are equal if they differ by at most `FloatWork::EPSILON`, relative to the larger one.
+/// This lets monitors complete and close even if the work submitted does not add up to the total exactly.
+#[derive(Debug, Clone, Copy)]
+pub struct FloatWork(f64);
+
+impl FloatWork {
+    /// The relative tolerance used when comparing amounts of work.
+    pub const EPSILON: f64 = 1e-9;
+
+    pub fn value(&self) -> f64 {
+        self.0
+    }
+
+    fn tolerance(a: f64, b: f64) -> f64 {
+        Self::EPSILON * a.abs().max(b.abs())
+    }
+
+    fn approx_eq(a: f64, b: f64) -> bool {
+        (a - b).abs() <= Self::tolerance(a, b)
+    }
+}
+
+impl Work for FloatWork {
+    type Type = f64;
+
+    fn new<A: Into<Self::Type>>(value: A) -> Self {
+        Self(value.into())
+    }
+
+    fn zero() -> Self {
+        Self(0.0)
+    }
+
+    fn min<'a>(a: &'a Self, b: &'a Self) -> &'a Self {
+        if a < b {
+            a
+        } else {
+            b
+        }
+    }
+
+    /// Never exceeds `of_parent_work`. Completing the child work results in exactly `of_parent_work`.
+    fn parent_work_done_when(
+        sub_work_done: Self,
+        of_total_sub_work: Self,
+        of_parent_work: Self,
+    ) -> Self {
+        if of_total_sub_work.0 == 0.0 {
+            return Self::zero();
+        }
+        let rel =
+            (sub_work_done.0 / of_total_sub_work.0 * of_parent_work.0).clamp(0.0, of_parent_work.0);
+        if Self::approx_eq(rel, of_parent_work.0) {
+            of_parent_work
+        } else {
+            Self(rel)
+        }
+    }
+}
+
+impl PartialEq for FloatWork {
+    fn eq(&self, other: &Self) -> bool {
+        Self::approx_eq(self.0, other.0)
+    }
+}
+
+impl PartialOrd for FloatWork {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self == other {
+            Some(Ordering::Equal)
+        } else {
+            self.0.partial_cmp(&other.0)
+        }
+    }
+}
+
+impl Display for FloatWork {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}", self.0))
+    }
+}
+
+impl Add for FloatWork {
+    type Output = Result<Self, AddError>;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let sum = self.0 + rhs.0;
+        if sum.is_finite() {
+            Ok(Self(sum))
+        } else {
+            Err(AddError::Overflow)
+        }
+    }
+}
+
+/// Differences within the tolerance of the operands are exactly zero.
+impl Sub for FloatWork {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        if Self::approx_eq(self.0, rhs.0) {
+            Self::zero()
+        } else {
+            Self(self.0 - rhs.0)
+        }
+    }
+}
+
+impl From<f64> for FloatWork {
+    fn from(value: f64) -> Self {
+        Self::new(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+
+    #[test]
+    fn float_trees_close_despite_rounding() {
+        let mut root = CallbackProgressMonitor::new("root", 1.0, |_: &FloatWork, _: &FloatWork| {});
+        {
+            let mut child = root.new_child("child", 0.3, 0.7);
+            for _ in 0..7 {
+                child.worked(0.1);
+            }
+            assert_eq!(child.remaining().as_ref(), &FloatWork::zero());
+            child.close().unwrap();
+        }
+        for _ in 0..7 {
+            root.worked(0.1);
+        }
+        assert_eq!(root.completed().as_ref(), &FloatWork::new(1.0));
+        root.close().unwrap();
+    }
+    #[test]
+    fn small_amounts_are_compared_relatively() {
+        assert_ne!(FloatWork::new(1e-12), FloatWork::zero());
+        assert_eq!(FloatWork::new(1e-12), FloatWork::new(1e-12 + 1e-24));
+
+        let mut mon =
+            CallbackProgressMonitor::new("root", 1e-12, |_: &FloatWork, _: &FloatWork| {});
+        assert!(mon.close().is_err());
+        for _ in 0..3 {
+            mon.worked(1e-12 / 3.0);
+        }
+        assert_eq!(mon.close(), Ok(CloseOutcome::Completed));
+    }
+}
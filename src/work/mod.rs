@@ -1,16 +1,35 @@
 use std::{
+    error::Error,
     fmt::{Debug, Display},
     ops::{Add, Sub},
 };
 
+pub mod bytes;
+pub mod duration;
+pub mod float;
 pub mod numeric;
 pub mod set;
 
-#[derive(Debug)]
-pub struct AddError {
-    pub msg: String,
+/// Reasons for two amounts of work not being addable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddError {
+    /// Both amounts contain the same item. Contains the debug representation of that item.
+    Duplicate { item: String },
+    /// The sum is not representable by the type of work.
+    Overflow,
 }
 
+impl Display for AddError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AddError::Duplicate { item } => write!(f, "Item {item} is present in both amounts."),
+            AddError::Overflow => f.write_str("Sum is not representable."),
+        }
+    }
+}
+
+impl Error for AddError {}
+
 pub trait Work:
     Sized
     + Debug
@@ -32,4 +51,10 @@ pub trait Work:
         of_total_sub_work: Self,
         of_parent_work: Self,
     ) -> Self;
+
+    /// Returns the debug representation of an item of this work which is not part of `total`.
+    /// Only work consisting of distinct items, like `SetWork`, can contain unknown items.
+    fn unknown_item(&self, _total: &Self) -> Option<String> {
+        None
+    }
 }
@@ -2,6 +2,7 @@ use std::{
     fmt::Debug,
     fmt::Display,
     ops::{Add, Sub},
+    time::Duration,
 };
 
 use num::{FromPrimitive, Num, ToPrimitive};
@@ -15,6 +16,21 @@ impl<T: Num + ToPrimitive + FromPrimitive + PartialOrd + Debug + Display + Clone
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub struct NumericWork<N: NumReq>(N);
 
+impl<N: NumReq> NumericWork<N> {
+    pub fn value(&self) -> &N {
+        &self.0
+    }
+}
+
+impl NumericWork<u64> {
+    /// Counts the nanoseconds of `duration`, saturating at `u64::MAX` (more than 584 years).
+    /// This lets children of numeric monitors be scaled in time,
+    /// e.g. `mon.new_child("poll", 10, NumericWork::from_nanos(Duration::from_secs(300)))`.
+    pub fn from_nanos(duration: Duration) -> Self {
+        Self::new(u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX))
+    }
+}
+
 impl<N: NumReq> Work for NumericWork<N> {
     type Type = N;
 
@@ -34,11 +50,17 @@ impl<N: NumReq> Work for NumericWork<N> {
         }
     }
 
+    /// Integers are scaled exactly, rounding down. Other numbers are scaled using `f64` arithmetic.
     fn parent_work_done_when(
         sub_work_done: Self,
         of_total_sub_work: Self,
         of_parent_work: Self,
     ) -> Self {
+        if let Some(exact) = scale_exact(&sub_work_done.0, &of_total_sub_work.0, &of_parent_work.0)
+        {
+            return Self::new(exact);
+        }
+
         let sub_work_done = sub_work_done.0.to_f64().expect("representable as f64");
         let of_total_sub_work = of_total_sub_work.0.to_f64().expect("representable as f64");
         let of_parent_work = of_parent_work.0.to_f64().expect("representable as f64");
@@ -49,6 +71,44 @@ impl<N: NumReq> Work for NumericWork<N> {
     }
 }
 
+/// Computes `floor(done / total * parent)` without loss of precision,
+/// if `N` is an integer type and all values are non-negative and representable as `u128`.
+fn scale_exact<N: NumReq>(done: &N, total: &N, parent: &N) -> Option<N> {
+    if !is_integer_type::<N>() {
+        return None;
+    }
+    let (done, total, parent) = (done.to_u128()?, total.to_u128()?, parent.to_u128()?);
+    if total == 0 {
+        return None;
+    }
+    N::from_u128(done.checked_mul(parent)? / total)
+}
+
+/// Adds `a` and `b`, returning `None` if the sum is not representable as `N`.
+/// Integers are added using `i128` or `u128` arithmetic, other numbers directly.
+fn checked_sum<N: NumReq>(a: &N, b: &N) -> Option<N> {
+    if is_integer_type::<N>() {
+        if let (Some(a), Some(b)) = (a.to_i128(), b.to_i128()) {
+            return N::from_i128(a.checked_add(b)?);
+        }
+        if let (Some(a), Some(b)) = (a.to_u128(), b.to_u128()) {
+            return N::from_u128(a.checked_add(b)?);
+        }
+    }
+    let sum = a.clone() + b.clone();
+    // Floating point numbers overflow to infinity.
+    let overflows = matches!(
+        (a.to_f64(), b.to_f64(), sum.to_f64()),
+        (Some(a), Some(b), Some(sum)) if a.is_finite() && b.is_finite() && !sum.is_finite()
+    );
+    (!overflows).then_some(sum)
+}
+
+fn is_integer_type<N: NumReq>() -> bool {
+    // Integer types truncate when converting from fractional numbers.
+    N::from_f64(0.5).map_or(false, |half| half == N::zero())
+}
+
 impl<N: NumReq> Display for NumericWork<N> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_fmt(format_args!("{}", self.0))
@@ -59,7 +119,9 @@ impl<N: NumReq> Add for NumericWork<N> {
     type Output = Result<Self, AddError>;
 
     fn add(self, rhs: Self) -> Self::Output {
-        Ok(Self::new(self.0 + rhs.0))
+        checked_sum(&self.0, &rhs.0)
+            .map(Self::new)
+            .ok_or(AddError::Overflow)
     }
 }
 
@@ -76,3 +138,36 @@ impl<N: NumReq> From<N> for NumericWork<N> {
         Self::new(value)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::work::{numeric::NumericWork, AddError, Work};
+
+    #[test]
+    fn addition_detects_overflow() {
+        let sum = NumericWork::<u8>::new(200u8) + NumericWork::<u8>::new(55u8);
+        assert_eq!(sum.unwrap(), NumericWork::<u8>::new(255u8));
+        let sum = NumericWork::<u8>::new(200u8) + NumericWork::<u8>::new(56u8);
+        assert_eq!(sum.unwrap_err(), AddError::Overflow);
+        let sum = NumericWork::<i8>::new(-100i8) + NumericWork::<i8>::new(-29i8);
+        assert_eq!(sum.unwrap_err(), AddError::Overflow);
+        let sum = NumericWork::<f64>::new(f64::MAX) + NumericWork::<f64>::new(f64::MAX);
+        assert_eq!(sum.unwrap_err(), AddError::Overflow);
+    }
+
+    #[test]
+    fn integers_are_scaled_exactly() {
+        let scaled = NumericWork::<u64>::parent_work_done_when(
+            NumericWork::new(u64::MAX - 1),
+            NumericWork::new(u64::MAX),
+            NumericWork::new(u64::MAX),
+        );
+        assert_eq!(scaled, NumericWork::new(u64::MAX - 1));
+        let scaled = NumericWork::<f64>::parent_work_done_when(
+            NumericWork::new(1.0),
+            NumericWork::new(3.0),
+            NumericWork::new(1.0),
+        );
+        assert_eq!(scaled, NumericWork::new(1.0 / 3.0));
+    }
+}
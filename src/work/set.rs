@@ -1,4 +1,5 @@
 use std::{
+    cmp::Ordering,
     collections::BTreeSet,
     fmt::{Debug, Display},
     ops::{Add, Sub},
@@ -10,9 +11,24 @@ pub trait SetReq: Debug + PartialEq + Eq + PartialOrd + Ord + Clone + Copy {}
 
 impl<T: Debug + PartialEq + Eq + PartialOrd + Ord + Clone + Copy> SetReq for T {}
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct SetWork<T: SetReq>(BTreeSet<T>);
 
+/// Sets are ordered by inclusion. A set is less than another set if it is a proper subset of it.
+impl<T: SetReq> PartialOrd for SetWork<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.0 == other.0 {
+            Some(Ordering::Equal)
+        } else if self.0.is_subset(&other.0) {
+            Some(Ordering::Less)
+        } else if self.0.is_superset(&other.0) {
+            Some(Ordering::Greater)
+        } else {
+            None
+        }
+    }
+}
+
 impl<T: SetReq> Display for SetWork<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_fmt(format_args!("{:?}", self.0))
@@ -62,36 +78,39 @@ impl<T: SetReq> Work for SetWork<T> {
         }
     }
 
+    /// The first items of `of_parent_work`, as many as correspond to the share of items done, rounding down.
     fn parent_work_done_when(
         sub_work_done: Self,
-        _of_total_sub_work: Self,
+        of_total_sub_work: Self,
         of_parent_work: Self,
     ) -> Self {
-        let mut partial = Self::zero();
-        for elem in sub_work_done.0.iter() {
-            if !of_parent_work.0.contains(elem) {
-                let _inserted = partial.0.insert(elem.clone());
-            }
+        let total = of_total_sub_work.0.len();
+        if total == 0 {
+            return Self::zero();
         }
-        partial
+        let done = sub_work_done.0.len() * of_parent_work.0.len() / total;
+        SetWork(of_parent_work.0.into_iter().take(done).collect())
+    }
+
+    fn unknown_item(&self, total: &Self) -> Option<String> {
+        self.0
+            .iter()
+            .find(|it| !total.0.contains(it))
+            .map(|it| format!("{it:?}"))
     }
 }
 
 impl<T: SetReq> Add for SetWork<T> {
     type Output = Result<Self, AddError>;
 
-    fn add(mut self, mut rhs: Self) -> Self::Output {
-        self.0.append(&mut rhs.0);
-
-        for r in rhs.0 {
-            if self.0.contains(&r) {
-                return Err(AddError {
-                    msg: format!("Element {r:?} is already present."),
-                });
-            }
+    fn add(mut self, rhs: Self) -> Self::Output {
+        if let Some(r) = rhs.0.iter().find(|r| self.0.contains(r)) {
+            return Err(AddError::Duplicate {
+                item: format!("{r:?}"),
+            });
         }
-
-        Ok(SetWork(self.0))
+        self.0.extend(rhs.0);
+        Ok(self)
     }
 }
 
@@ -99,7 +118,33 @@ impl<T: SetReq> Sub for SetWork<T> {
     type Output = Self;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        let diff = self.0.difference(&rhs.0).map(|it| *it).collect();
+        let diff = self.0.difference(&rhs.0).copied().collect();
         SetWork(diff)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::*;
+
+    #[test]
+    fn invalid_items_are_rejected() {
+        let mut mon =
+            CallbackProgressMonitor::new("root", &[1, 2], |_: &SetWork<u8>, _: &SetWork<u8>| {});
+        mon.try_worked(2).unwrap();
+        assert!(matches!(
+            mon.try_worked(2),
+            Err(WorkError::DuplicateItem { item, .. }) if item == "2"
+        ));
+        assert!(matches!(
+            mon.try_worked(3),
+            Err(WorkError::UnknownItem { item, .. }) if item == "3"
+        ));
+        mon.try_worked(1).unwrap();
+        mon.close().unwrap();
+        assert!(matches!(
+            mon.try_worked(2),
+            Err(WorkError::Closed { .. })
+        ));
+    }
+}